@@ -0,0 +1,59 @@
+use std::process::exit;
+use corewars_core::load_file::Warrior;
+use emars::{LoadError, Mars, MarsConfig, MatchResult, Severity};
+use emars::sim::{check_warriors, parse_warriors};
+
+fn warrior_title(warrior: &Warrior) -> String {
+    let name = warrior.metadata.name.clone().unwrap_or_default();
+    match &warrior.metadata.author {
+        Some(author) => format!("{name} by {author}"),
+        None => name,
+    }
+}
+
+pub(crate) fn exit_with(error: LoadError) -> ! { // prints every diagnostic of a failed load and exits, as there's no window to show them in
+    for diagnostic in error.diagnostics { eprintln!("{diagnostic}"); }
+    exit(1);
+}
+
+pub fn run_batch(warrior_paths: &[String], config: MarsConfig, rounds: usize) {
+    if let Err(message) = config.check() { exit_with(LoadError::from(message)); }
+    let (warriors, mut diagnostics) = parse_warriors(warrior_paths, &[]).unwrap_or_else(|error| exit_with(error));
+    diagnostics.extend(check_warriors(&warriors, &config));
+    if diagnostics.iter().any(|diagnostic| diagnostic.severity == Severity::Error) { exit_with(LoadError { diagnostics }); }
+    for warning in diagnostics { eprintln!("{warning}"); }
+    let warriors: Vec<Warrior> = warriors.into_iter().map(|parsed| parsed.warrior).collect();
+    let teams = warriors.len();
+
+    let mut wins: Vec<usize> = vec![0; teams];
+    let mut ties: Vec<usize> = vec![0; teams];
+    let mut scores: Vec<usize> = vec![0; teams];
+    let seed = config.seed;
+    let mut mars = Mars::new(config, &warriors).unwrap_or_else(|error| exit_with(error));
+    for round in 0..rounds {
+        // the warriors' offsets are re-randomized every round, while their P-spaces carry over
+        if round > 0 { mars.next_round(&warriors).unwrap_or_else(|error| exit_with(error)); }
+        match mars.run_to_end() {
+            MatchResult::Win(team) => {
+                wins[team as usize] += 1;
+                scores[team as usize] += if teams == 2 { 3 } else { teams * teams - 1 };
+            }
+            _ => {
+                let survivors = mars.living_teams();
+                for team in (0..teams).filter(|team| mars.is_alive(*team)) {
+                    ties[team] += 1;
+                    scores[team] += if teams == 2 { 1 } else { (teams * teams - 1) / survivors };
+                }
+            }
+        }
+    }
+
+    // scored like pMARS: 3 points per win and 1 per tie with two warriors,
+    // and (warriors^2 - 1) / survivors points per survived round in a melee
+    for (team, warrior) in warriors.iter().enumerate() {
+        let losses = rounds - wins[team] - ties[team];
+        println!("{} scores {}", warrior_title(warrior), scores[team]);
+        println!("Results: {} {} {}", wins[team], losses, ties[team]);
+    }
+    println!("Seed: {seed}");
+}
@@ -0,0 +1,166 @@
+use std::fmt;
+use emars::Mars;
+use emars::sim::Opcode;
+use crate::gui::display_instruction;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum BreakpointKind {
+    Address(usize), // a process is about to execute this address
+    Range(usize, usize), // a process is about to execute an address from the first to the second, wrapping around the core if the first is bigger
+    Opcode(Opcode), // a process is about to execute this opcode
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct Breakpoint {
+    pub team: Option<u8>, // the team whose processes are watched, or every team if None
+    pub kind: BreakpointKind,
+}
+
+impl Default for Breakpoint {
+    fn default() -> Breakpoint {
+        return Breakpoint { team: None, kind: BreakpointKind::Address(0) };
+    }
+}
+
+impl fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.team {
+            Some(team) => write!(f, "Team {team}: ")?,
+            None => write!(f, "Any team: ")?,
+        }
+        match self.kind {
+            BreakpointKind::Address(address) => write!(f, "address {address:04}"),
+            BreakpointKind::Range(start, end) => write!(f, "addresses {start:04} to {end:04}"),
+            BreakpointKind::Opcode(opcode) => write!(f, "opcode {opcode}"),
+        }
+    }
+}
+
+impl Breakpoint {
+    fn matches(&self, pointer: usize, opcode: Opcode) -> bool {
+        return match self.kind {
+            BreakpointKind::Address(address) => pointer == address,
+            BreakpointKind::Range(start, end) if start <= end => (start..=end).contains(&pointer),
+            BreakpointKind::Range(start, end) => pointer >= start || pointer <= end,
+            BreakpointKind::Opcode(other) => opcode == other,
+        };
+    }
+
+    pub fn hit(&self, mars: &Mars) -> Option<String> { // why this breakpoint stops the match before its next cycle, if it does
+        for (team, process_queue) in mars.teams_process_queues.iter().enumerate() {
+            if self.team.is_some_and(|other| other as usize != team) { continue; }
+            // the first process in each queue is the one the next cycle steps
+            let Some(process) = process_queue.front() else { continue; };
+            let instruction = mars.core[process.pointer];
+            if self.matches(process.pointer, instruction.opcode) {
+                return Some(format!(
+                    "Team {team} ({}) is about to execute {} at {:04} ({self})",
+                    mars.warrior_names[team], display_instruction(instruction, mars.config.coresize).trim_end(), process.pointer
+                ));
+            }
+        }
+        return None;
+    }
+}
+
+pub fn hit_breakpoint(breakpoints: &[Breakpoint], mars: &Mars) -> Option<String> { // why the first breakpoint hit stops the match, if any is
+    return breakpoints.iter().find_map(|breakpoint| breakpoint.hit(mars));
+}
+
+#[derive(Clone, Copy, PartialEq, Default)]
+pub struct Watchpoint {
+    pub start: usize, // the first address watched
+    pub end: usize, // the last address watched, wrapping around the core if it's smaller than start
+}
+
+impl fmt::Display for Watchpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.start == self.end { return write!(f, "writes to {:04}", self.start); }
+        write!(f, "writes to {:04} to {:04}", self.start, self.end)
+    }
+}
+
+impl Watchpoint {
+    fn contains(&self, address: usize) -> bool {
+        if self.start <= self.end { return (self.start..=self.end).contains(&address); }
+        return address >= self.start || address <= self.end;
+    }
+
+    pub fn hit(&self, mars: &Mars) -> Option<String> { // who wrote to a watched address in the last cycle, if anyone did
+        for record in &mars.last_steps {
+            if let Some(address) = record.written_addresses().find(|address| self.contains(*address)) {
+                let team = record.process.team as usize;
+                return Some(format!(
+                    "Team {team} ({}) wrote to {address:04} with {} at {:04} ({self})",
+                    mars.warrior_names[team], display_instruction(record.instruction, mars.config.coresize).trim_end(), record.process.pointer
+                ));
+            }
+        }
+        return None;
+    }
+}
+
+pub fn hit_watchpoint(watchpoints: &[Watchpoint], mars: &Mars) -> Option<String> { // why the first watchpoint hit stops the match, if any is
+    return watchpoints.iter().find_map(|watchpoint| watchpoint.hit(mars));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use emars::MarsConfig;
+
+    fn load(sources: &[&str], positions: &[usize]) -> Mars { // a match in a core of 8000 with the warriors at positions
+        let mut config = MarsConfig::new(8000);
+        config.positions = positions.iter().map(|position| Some(*position)).collect();
+        let paths = vec!["test.red".to_string(); sources.len()];
+        let sources: Vec<Option<String>> = sources.iter().map(|source| Some(source.to_string())).collect();
+        return Mars::load_edited(config, &paths, &sources).unwrap_or_else(|error| panic!("{error}")).0;
+    }
+
+    #[test]
+    fn ranges_wrap_around_the_core() {
+        let range = Breakpoint { team: None, kind: BreakpointKind::Range(7990, 10) };
+        for pointer in [7990, 7999, 0, 10] {
+            assert!(range.matches(pointer, Opcode::Dat), "{pointer}");
+        }
+        for pointer in [11, 4000, 7989] {
+            assert!(!range.matches(pointer, Opcode::Dat), "{pointer}");
+        }
+        let range = Breakpoint { team: None, kind: BreakpointKind::Range(10, 20) };
+        assert!(range.matches(10, Opcode::Dat) && range.matches(20, Opcode::Dat));
+        assert!(!range.matches(9, Opcode::Dat) && !range.matches(21, Opcode::Dat));
+    }
+
+    #[test]
+    fn breakpoints_only_watch_their_team() {
+        let mars = load(&["jmp 0", "mov 0, 1"], &[0, 4000]);
+        let at = |team: Option<u8>, address: usize| Breakpoint { team, kind: BreakpointKind::Address(address) };
+        assert!(at(None, 4000).hit(&mars).is_some());
+        assert!(at(Some(1), 4000).hit(&mars).is_some());
+        assert!(at(Some(0), 4000).hit(&mars).is_none());
+        assert!(at(Some(1), 0).hit(&mars).is_none());
+        let mov = |team: Option<u8>| Breakpoint { team, kind: BreakpointKind::Opcode(Opcode::Mov) };
+        assert!(mov(Some(1)).hit(&mars).is_some());
+        assert!(mov(Some(0)).hit(&mars).is_none());
+    }
+
+    #[test]
+    fn watchpoint_ranges_wrap_around_the_core() {
+        let watchpoint = Watchpoint { start: 7990, end: 10 };
+        assert!([7990, 7999, 0, 10].iter().all(|address| watchpoint.contains(*address)));
+        assert!(![11, 4000, 7989].iter().any(|address| watchpoint.contains(*address)));
+        let watchpoint = Watchpoint { start: 5, end: 5 };
+        assert!(watchpoint.contains(5) && !watchpoint.contains(4) && !watchpoint.contains(6));
+    }
+
+    #[test]
+    fn predecrements_hit_watchpoints() {
+        // JMP doesn't write to its target, so only the decrement of the cell at 5 is a write
+        let mut mars = load(&["jmp 0, <5", "jmp 0"], &[0, 4000]);
+        mars.step();
+        assert!(Watchpoint { start: 5, end: 5 }.hit(&mars).is_some());
+        assert!(Watchpoint { start: 4, end: 4 }.hit(&mars).is_none());
+        assert!(Watchpoint { start: 6, end: 4000 }.hit(&mars).is_none());
+        assert!(Watchpoint { start: 7999, end: 5 }.hit(&mars).is_some());
+    }
+}
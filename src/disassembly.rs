@@ -0,0 +1,93 @@
+use eframe::egui;
+use egui::*;
+use emars::Mars;
+use crate::EmarsApp;
+use crate::gui::{display_instruction, pointing_teams, team_color};
+
+// the state of the Disassembly window
+pub(crate) struct Disassembly {
+    pub follow: Option<u8>, // the team whose next process the listing scrolls to after every step, if any
+    followed: Option<usize>, // the address the listing last scrolled to while following
+    jump_address: usize, // the address typed into the jump field
+    scroll_to: Option<usize>, // the address to scroll to next frame
+}
+
+impl Disassembly {
+    pub fn new() -> Disassembly {
+        return Disassembly { follow: Some(0), followed: None, jump_address: 0, scroll_to: None };
+    }
+
+    pub fn update(&mut self, mars: &Mars) { // scrolls to the followed team's next process whenever it moves
+        let Some(team) = self.follow else { return; };
+        let next = mars.teams_process_queues.get(team as usize).and_then(|process_queue| process_queue.front()).map(|process| process.pointer);
+        if next.is_some() && next != self.followed {
+            self.scroll_to = next;
+        }
+        self.followed = next;
+    }
+}
+
+pub fn disassembly_window(app: &mut EmarsApp, context: &Context) {
+    Window::new("Disassembly")
+    .default_open(false)
+    .default_height(400.)
+    .show(context, |ui| {
+        let mars = &app.mars;
+        let coresize = mars.config.coresize;
+        let disassembly = &mut app.disassembly;
+
+        ui.horizontal(|ui| {
+            ui.label("Follow: ");
+            let follow_name = |follow: Option<u8>| match follow {
+                Some(team) => format!("Team {team}: {}", mars.warrior_names.get(team as usize).map_or("", String::as_str)),
+                None => "Off".to_string(),
+            };
+            let old_follow = disassembly.follow;
+            ComboBox::from_id_salt("disassembly_follow")
+            .selected_text(follow_name(disassembly.follow))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut disassembly.follow, None, follow_name(None));
+                for team in 0..mars.teams_process_queues.len() as u8 {
+                    ui.selectable_value(&mut disassembly.follow, Some(team), follow_name(Some(team)));
+                }
+            });
+            // following another team jumps to its process straight away
+            if disassembly.follow != old_follow { disassembly.followed = None; }
+
+            ui.separator();
+            let address_field = ui.add(DragValue::new(&mut disassembly.jump_address).range(0..=coresize.saturating_sub(1)));
+            let entered = address_field.lost_focus() && ui.input(|input| input.key_pressed(Key::Enter));
+            if ui.button("Jump").clicked() || entered {
+                // following would scroll straight back to the process, so it's turned off
+                disassembly.follow = None;
+                disassembly.scroll_to = Some(disassembly.jump_address);
+            }
+        });
+        ui.separator();
+
+        let pointed = pointing_teams(mars);
+        let digits = coresize.saturating_sub(1).to_string().len();
+        let row_height = ui.text_style_height(&TextStyle::Monospace);
+        let mut scroll_area = ScrollArea::vertical().auto_shrink([false, false]);
+        if let Some(address) = disassembly.scroll_to.take() {
+            // the address is scrolled to the middle of the listing
+            let row_height_with_spacing = row_height + ui.spacing().item_spacing.y;
+            let offset = address as f32 * row_height_with_spacing - (ui.available_height() - row_height) / 2.;
+            scroll_area = scroll_area.vertical_scroll_offset(offset.max(0.));
+        }
+        scroll_area.show_rows(ui, row_height, coresize, |ui, rows| {
+            for address in rows {
+                // the first process in each queue is the one its team steps next
+                let next = mars.teams_process_queues.iter().any(|process_queue| process_queue.front().is_some_and(|process| process.pointer == address));
+                let marker = if next { '>' } else { ' ' };
+                let mut text = RichText::new(format!("{marker} {address:0digits$}  {}", display_instruction(mars.core[address], coresize))).monospace();
+                if let Some(team) = pointed[address] {
+                    text = text.color(team_color(team));
+                }
+                if ui.selectable_label(app.pinned_cell == Some(address), text).on_hover_text("Click to show this cell in Cell Details").clicked() {
+                    app.pinned_cell = Some(address);
+                }
+            }
+        });
+    });
+}
@@ -0,0 +1,158 @@
+use std::fs::{read_to_string, write};
+use eframe::egui;
+use egui::*;
+use egui::text::LayoutJob;
+use emars::{Diagnostic, Severity};
+use emars::sim::{MODIFIERS, Opcode, PSEUDO_OPCODES, check_warriors, parse_warrior_source};
+use crate::EmarsApp;
+
+const ADDRESS_MODES: [char; 8] = ['#', '$', '@', '<', '>', '*', '{', '}'];
+
+const OPCODE_COLOR: Color32 = Color32::from_rgb(0x11, 0xc1, 0xfc);
+const PSEUDO_OPCODE_COLOR: Color32 = Color32::from_rgb(0xd0, 0x70, 0xff);
+const MODIFIER_COLOR: Color32 = Color32::from_rgb(0x80, 0xe0, 0x80);
+const ADDRESS_MODE_COLOR: Color32 = Color32::from_rgb(0xff, 0xa0, 0x40);
+const NUMBER_COLOR: Color32 = Color32::YELLOW;
+const COMMENT_COLOR: Color32 = Color32::GRAY;
+const ERROR_LINE_COLOR: Color32 = Color32::from_rgb(0x50, 0x10, 0x10);
+
+#[derive(Default)]
+pub(crate) struct WarriorEditor {
+    pub open: bool, // whether the editor window is shown
+    source: Option<String>, // the text being edited, read from the warrior's file the first time the editor opens
+    pub assembled: Option<String>, // the text last assembled into the match, which is loaded instead of the file until it's saved
+    diagnostics: Vec<Diagnostic>, // the errors and warnings from the last time the source was assembled
+}
+
+pub fn open_editor(app: &mut EmarsApp, team: usize) {
+    let editor = &mut app.editors[team];
+    if editor.source.is_none() {
+        // an unreadable file just starts out empty, saving it will create it
+        editor.source = Some(read_to_string(&app.warrior_paths[team]).unwrap_or_default());
+    }
+    editor.open = true;
+}
+
+fn format_for(color: Color32, background: Color32) -> TextFormat {
+    return TextFormat { font_id: FontId::monospace(12.), color, background, ..Default::default() };
+}
+
+fn highlight_redcode(source: &str, error_lines: &[usize], default_color: Color32) -> LayoutJob { // colors opcodes, modifiers, modes, numbers and comments
+    let mut job = LayoutJob::default();
+    for (i, line) in source.split_inclusive('\n').enumerate() {
+        let background = if error_lines.contains(&(i + 1)) { ERROR_LINE_COLOR } else { Color32::TRANSPARENT };
+        let (code, comment) = line.split_at(line.find(';').unwrap_or(line.len()));
+
+        let mut after_opcode = false; // whether the last word was an opcode, so a following .X is its modifier
+        let mut rest = code;
+        while let Some(c) = rest.chars().next() {
+            let length: usize;
+            let color: Color32;
+            if c.is_alphanumeric() || c == '_' {
+                length = rest.find(|c: char| !(c.is_alphanumeric() || c == '_')).unwrap_or(rest.len());
+                let word = &rest[..length];
+                let is_opcode = Opcode::ALL.iter().any(|opcode| opcode.to_string().eq_ignore_ascii_case(word));
+                if c.is_ascii_digit() {
+                    color = NUMBER_COLOR;
+                } else if is_opcode {
+                    color = OPCODE_COLOR;
+                } else if PSEUDO_OPCODES.iter().any(|pseudo_opcode| pseudo_opcode.eq_ignore_ascii_case(word)) {
+                    color = PSEUDO_OPCODE_COLOR;
+                } else {
+                    color = default_color;
+                }
+                after_opcode = is_opcode;
+            } else if c == '.' && after_opcode {
+                let word_length = rest[1..].find(|c: char| !c.is_alphanumeric()).unwrap_or(rest.len() - 1);
+                let is_modifier = MODIFIERS.iter().any(|modifier| modifier.eq_ignore_ascii_case(&rest[1..1 + word_length]));
+                length = if is_modifier { 1 + word_length } else { 1 };
+                color = if is_modifier { MODIFIER_COLOR } else { default_color };
+                after_opcode = false;
+            } else {
+                length = c.len_utf8();
+                color = if ADDRESS_MODES.contains(&c) { ADDRESS_MODE_COLOR } else { default_color };
+                if !c.is_whitespace() { after_opcode = false; }
+            }
+            job.append(&rest[..length], 0., format_for(color, background));
+            rest = &rest[length..];
+        }
+        job.append(comment, 0., format_for(COMMENT_COLOR, background));
+    }
+    return job;
+}
+
+pub fn editor_windows(app: &mut EmarsApp, context: &Context) {
+    let mut reload: Option<(usize, Option<String>)> = None; // the team to reload the match for, and what it had assembled before
+    let config = &app.mars.config;
+    for (team, editor) in app.editors.iter_mut().enumerate() {
+        let path = &app.warrior_paths[team];
+        let Some(source) = &mut editor.source else { continue; };
+        let error_lines: Vec<usize> = editor.diagnostics.iter()
+            .filter(|diagnostic| diagnostic.severity == Severity::Error)
+            .filter_map(|diagnostic| diagnostic.line)
+            .collect();
+
+        Window::new(format!("Editor: team {team}"))
+        .open(&mut editor.open)
+        .default_size(vec2(420., 480.))
+        .show(context, |ui| {
+            ui.label(path);
+            ui.horizontal(|ui| {
+                if ui.button("Assemble & Reload").on_hover_text("Checks the warrior against the match settings, then reloads the match with it, keeping the seed and start positions. The file isn't changed until it's saved").clicked() {
+                    editor.diagnostics = match parse_warrior_source(path, source) {
+                        Ok((parsed, mut diagnostics)) => {
+                            // checked against the match first, so a warrior the reload would reject doesn't replace the one running
+                            diagnostics.extend(check_warriors(&[parsed], config));
+                            if !diagnostics.iter().any(|diagnostic| diagnostic.severity == Severity::Error) {
+                                reload = Some((team, editor.assembled.replace(source.clone())));
+                            }
+                            diagnostics
+                        }
+                        Err(error) => error.diagnostics,
+                    };
+                }
+                if ui.button("Save").on_hover_text("Writes the text to the warrior's file").clicked() {
+                    match write(path, source.as_str()) {
+                        // the file has the newest text now, so it's what the next reload loads
+                        Ok(_) => editor.assembled = None,
+                        Err(error) => editor.diagnostics.push(Diagnostic { severity: Severity::Error, path: Some(path.clone()), line: None, message: format!("could not save the file: {error}") }),
+                    }
+                }
+            });
+
+            for diagnostic in &editor.diagnostics {
+                let color = match diagnostic.severity {
+                    Severity::Warning => Color32::YELLOW,
+                    Severity::Error => Color32::RED,
+                };
+                match diagnostic.line {
+                    Some(line) => ui.colored_label(color, format!("line {line}: {}", diagnostic.message)),
+                    None => ui.colored_label(color, &diagnostic.message),
+                };
+            }
+            ui.separator();
+
+            let default_color = ui.visuals().text_color();
+            let mut layouter = |ui: &Ui, text: &dyn TextBuffer, wrap_width: f32| {
+                let mut job = highlight_redcode(text.as_str(), &error_lines, default_color);
+                job.wrap.max_width = wrap_width;
+                return ui.fonts(|fonts| fonts.layout_job(job));
+            };
+            ScrollArea::vertical().show(ui, |ui| {
+                ui.add(TextEdit::multiline(source)
+                    .code_editor()
+                    .desired_width(f32::INFINITY)
+                    .desired_rows(24)
+                    .layouter(&mut layouter));
+            });
+        });
+    }
+    // reloaded like the file watcher does, so an edit doesn't move the warriors or change the seed
+    if let Some((team, previous)) = reload {
+        if !app.reload() {
+            // the match it was checked against is still the one running, so the warrior it replaced goes back too
+            app.editors[team].assembled = previous;
+            app.editors[team].diagnostics.extend(app.diagnostics.iter().filter(|diagnostic| diagnostic.severity == Severity::Error).cloned());
+        }
+    }
+}
@@ -1,12 +1,27 @@
-use std::cmp::max;
 use eframe::egui;
 use egui::*;
+use emars::{Mars, MarsConfig, MatchResult, Owner, Preset, Severity};
+use emars::sim::{Instruction, Field, Opcode};
 use crate::EmarsApp;
-use crate::sim::{Instruction, Field, full_step};
+use crate::editor::open_editor;
+use crate::breakpoint::BreakpointKind;
+use crate::heatmap::HeatmapMode;
 
-const TEAM_COLORS: [Color32; 4] = [Color32::GREEN, Color32::from_rgb(0x11, 0xc1, 0xfc), Color32::YELLOW, Color32::RED];
+const TEAM_COLORS: [Color32; 4] = [Color32::GREEN, Color32::from_rgb(0x11, 0xc1, 0xfc), Color32::YELLOW, Color32::from_rgb(0xd0, 0x70, 0xff)];
 
-fn display_instruction(instruction: Instruction, coresize: usize) -> String {
+pub fn team_color(team: u8) -> Color32 {
+    if (team as usize) < TEAM_COLORS.len() { return TEAM_COLORS[team as usize]; }
+    // past the fixed colors, hues are spread by the golden ratio so neighbouring teams stay distinguishable
+    let hue = (team as f32 * 0.618034) % 1.;
+    return ecolor::Hsva::new(hue, 0.8, 1., 1.).into();
+}
+
+fn owner_color(team: u8) -> Color32 { // a darker team color, so process outlines in the team color still stand out
+    let color = team_color(team);
+    return Color32::from_rgb((color.r() as f32 * 0.6) as u8, (color.g() as f32 * 0.6) as u8, (color.b() as f32 * 0.6) as u8);
+}
+
+pub fn display_instruction(instruction: Instruction, coresize: usize) -> String {
     format!(
         // Example output:
         // MOV.AB  $-100,  $1
@@ -26,127 +41,209 @@ fn display_field(field: Field, coresize_isize: isize) -> String {
     format!("{}{}", field.address_mode, value)
 }
 
+pub fn pointing_teams(mars: &Mars) -> Vec<Option<u8>> { // the team of a process pointing at each cell, if one does
+    let mut pointed: Vec<Option<u8>> = vec![None; mars.config.coresize];
+    for process_queue in &mars.teams_process_queues {
+        for process in process_queue {
+            pointed[process.pointer] = Some(process.team);
+        }
+    }
+    return pointed;
+}
+
+const MIN_CELL_SIZE: f32 = 2.; // the furthest the core view zooms out, in pixels per cell
+const MAX_CELL_SIZE: f32 = 40.; // the furthest the core view zooms in, in pixels per cell
+const LABEL_FONT_SIZE: f32 = 10.;
+
+// how far the core view is zoomed in
+pub(crate) struct CoreViewport {
+    pub cell_size: f32, // the width and height of each cell, in pixels
+    pub fit: bool, // whether the cell size follows the window so the whole core is visible at once
+    scroll_to: Option<f32>, // where to scroll next frame, so the cell under the mouse stays put while zooming
+}
+
+impl CoreViewport {
+    pub fn new() -> CoreViewport {
+        return CoreViewport { cell_size: 10., fit: true, scroll_to: None };
+    }
+}
+
+fn columns_for(width: f32, cell_size: f32) -> usize { // how many cells fit in a row
+    return ((width / cell_size).floor() as usize).max(1);
+}
+
+fn fit_cell_size(coresize: usize, width: f32, height: f32) -> f32 { // the largest cell size that shows the whole core at once
+    let mut cell_size = (width * height / coresize as f32).sqrt().clamp(MIN_CELL_SIZE, MAX_CELL_SIZE);
+    // the cells rarely fill the last row exactly, so shrink until that one fits as well
+    while cell_size > MIN_CELL_SIZE && coresize.div_ceil(columns_for(width, cell_size)) as f32 * cell_size > height {
+        cell_size -= 0.25;
+    }
+    return cell_size.max(MIN_CELL_SIZE);
+}
+
 pub fn core_view(app: &mut EmarsApp, context: &Context) {
-    let default_size: Vec2;
-    let label_font_size: f32;
-    let square_size_inside: f32;
-    let square_size_outside: f32;
-    let x_margin: f32;
-    let y_margin: f32;
-    let stroke_size: f32;
-    match app.core_view_size {
-        0 => (default_size, label_font_size, square_size_inside, square_size_outside, x_margin, y_margin, stroke_size) = (vec2(519., 416.), 6., 4., 5., 20., 16., 1.),
-        1 => (default_size, label_font_size, square_size_inside, square_size_outside, x_margin, y_margin, stroke_size) = (vec2(822., 655.), 8., 6., 8., 24., 17., 1.5),
-        2 => (default_size, label_font_size, square_size_inside, square_size_outside, x_margin, y_margin, stroke_size) = (vec2(1026., 817.), 10., 8., 10., 28., 18., 2.),
-        _ => panic!("Invalid core view size of {}", app.core_view_size)
-    };
     Window::new("Core View")
-    .default_size(default_size)
+    .default_size(vec2(1026., 817.))
     .show(context, |ui|{
-        
-        let painter_width = ui.available_width();
-        let painter_height = max((((app.coresize * 5) as f32 / painter_width).ceil() + 5.) as usize, ui.available_height() as usize) as f32;
-        
-        let (response, painter) = ui.allocate_painter(vec2(painter_width, painter_height), Sense::hover());
-        let window_width = response.rect.width();
-        let window_height = response.rect.height();
-        // println!("{window_width} x {window_height}s");
-
-        painter.text(
-            pos2(response.rect.min.x, response.rect.min.y + 16.),
-            Align2::LEFT_TOP,
-            "0000",
-            FontId::monospace(label_font_size),
-            Color32::LIGHT_GRAY
-        );
+        let coresize = app.mars.config.coresize;
+        let viewport = &mut app.core_viewport;
+        ui.horizontal(|ui| {
+            ui.label("Heatmap: ");
+            ComboBox::from_id_salt("heatmap_mode")
+            .selected_text(app.heatmap.mode.name())
+            .show_ui(ui, |ui| {
+                for mode in HeatmapMode::ALL {
+                    ui.selectable_value(&mut app.heatmap.mode, mode, mode.name());
+                }
+            });
+            ui.separator();
+            let zoom_slider = Slider::new(&mut viewport.cell_size, MIN_CELL_SIZE..=MAX_CELL_SIZE).logarithmic(true).text("Zoom");
+            if ui.add(zoom_slider).on_hover_text("Ctrl + mouse wheel zooms too, dragging or scrolling the core pans it").changed() {
+                viewport.fit = false;
+            }
+            ui.checkbox(&mut viewport.fit, "Fit to window");
+        });
 
-        let hovered: bool;
+        // the hovered cell is described on a line reserved above the core, which is only laid out below
+        let (hover_rect, _) = ui.allocate_exact_size(vec2(ui.available_width(), 14.), Sense::hover());
         let mut hovered_text = String::new();
-        let hover_pos: Pos2 = response.hover_pos().unwrap_or_else(|| pos2(0., 0.));
-        if hover_pos == pos2(0., 0.) {
-            hovered = false;
-        } else {
-            hovered = true;
-        }
 
-        // println!("hover_pos: {} x {}", hover_pos.x, hover_pos.y);
+        let label_font = FontId::monospace(LABEL_FONT_SIZE);
+        let label_digits = coresize.saturating_sub(1).to_string().len();
+        let label_width = ui.fonts(|fonts| fonts.glyph_width(&label_font, '0')) * label_digits as f32 + 4.;
+
+        // found once instead of searching the queues for every cell
+        let pointed = pointing_teams(&app.mars);
 
-        let mut x = response.rect.min.x + x_margin; // calculates *objective* x position
-        let mut y = response.rect.min.y + y_margin; // calculates *objective* y position
+        let pinned_cell = &mut app.pinned_cell;
+        let mut scroll_area = ScrollArea::vertical().auto_shrink([false, false]);
+        if let Some(offset) = viewport.scroll_to.take() { scroll_area = scroll_area.vertical_scroll_offset(offset); }
+        scroll_area.show_viewport(ui, |ui, visible| {
+            let cells_width = ui.available_width() - label_width;
+            if viewport.fit { viewport.cell_size = fit_cell_size(coresize, cells_width, visible.height()); }
+            let cell_size = viewport.cell_size;
+            let columns = columns_for(cells_width, cell_size);
+            let rows = coresize.div_ceil(columns);
+
+            let (response, painter) = ui.allocate_painter(vec2(ui.available_width(), rows as f32 * cell_size), Sense::click());
+            let origin = response.rect.min;
+            let gap = if cell_size >= 4. { cell_size / 5. } else { 0. }; // the space between neighbouring cells
+            let stroke_size = (cell_size / 5.).max(1.);
+            // when rows are shorter than the labels, only every few rows are labeled
+            let label_every = ((LABEL_FONT_SIZE / cell_size).ceil() as usize).next_power_of_two();
+
+            let cell_at = |pos: Pos2| -> Option<usize> { // the cell drawn at pos, if there is one
+                let column = ((pos.x - origin.x - label_width) / cell_size).floor();
+                let row = ((pos.y - origin.y) / cell_size).floor();
+                if column < 0. || column >= columns as f32 || row < 0. { return None; }
+                let i = row as usize * columns + column as usize;
+                return (i < coresize).then_some(i);
+            };
+            let hovered_cell = response.hover_pos().and_then(cell_at);
+            if response.clicked() {
+                if let Some(i) = response.interact_pointer_pos().and_then(cell_at) { *pinned_cell = Some(i); }
+            }
 
-        for i in 0..app.coresize {
-            let mut stroke = Stroke::NONE;
+            // only the rows scrolled into view are drawn, so huge cores stay fast
+            let first_row = (visible.min.y / cell_size).floor() as usize;
+            let last_row = ((visible.max.y / cell_size).ceil() as usize).min(rows);
+            for row in first_row..last_row {
+                let y = origin.y + row as f32 * cell_size;
 
-            // checks if square is being pointed to by any processes
-            for process_queue in &app.teams_process_queues {
-                for process in process_queue {
-                    if process.pointer == i {
-                        stroke = Stroke::new(stroke_size, TEAM_COLORS[process.team as usize]);
+                if row % label_every == 0 {
+                    let label_color: Color32;
+                    if (row / label_every).is_multiple_of(4) {
+                        label_color = Color32::LIGHT_GRAY;
+                    } else if (row / label_every).is_multiple_of(2) {
+                        label_color = Color32::GRAY;
+                    } else {
+                        label_color = Color32::DARK_GRAY;
                     }
+                    painter.text(
+                        pos2(origin.x, (y + cell_size / 2.).max(origin.y + LABEL_FONT_SIZE / 2.)),
+                        Align2::LEFT_CENTER,
+                        format!("{:0label_digits$}", row * columns),
+                        label_font.clone(),
+                        label_color
+                    );
                 }
-            }
 
-            // checks if this square is being hovered
-            if hovered && x <= hover_pos.x && (x + square_size_outside) >= hover_pos.x && y <= hover_pos.y && (y + square_size_outside) >= hover_pos.y {
-                hovered_text = display_instruction(app.core[i], app.coresize);
-                // println!("hovered_text: {hovered_text}");
-                stroke = Stroke::new(stroke_size, Color32::YELLOW);
-            }
+                let row_start = row * columns;
+                for (column, pointing_team) in pointed[row_start..(row_start + columns).min(coresize)].iter().enumerate() {
+                    let i = row_start + column;
+                    let x = origin.x + label_width + column as f32 * cell_size;
 
-            let instruction_color: Color32;
-            if app.core[i] == app.default_instruction {
-                instruction_color = Color32::DARK_GRAY;
-            } else {
-                instruction_color = Color32::from_rgb(200, 0, 0);
-            }
+                    // outlines the cell if a process points to it, or if it's being hovered
+                    let mut stroke = Stroke::NONE;
+                    if let Some(team) = *pointing_team {
+                        stroke = Stroke::new(stroke_size, team_color(team));
+                    }
+                    if *pinned_cell == Some(i) {
+                        stroke = Stroke::new(stroke_size, Color32::WHITE);
+                    }
+                    if hovered_cell == Some(i) {
+                        stroke = Stroke::new(stroke_size, Color32::YELLOW);
+                    }
+
+                    // cells are colored by the team that last wrote to them, like in pMARS
+                    let mut instruction_color: Color32;
+                    if let Some(owner) = app.mars.owners[i] {
+                        instruction_color = owner_color(owner.team);
+                    } else if app.mars.core[i] == app.mars.config.default_instruction {
+                        instruction_color = Color32::DARK_GRAY;
+                    } else {
+                        instruction_color = Color32::from_rgb(200, 0, 0);
+                    }
 
-            // draws the rectangle at pos (x, y) and size (4, 4) in red
-            painter.rect(
-                Rect::from_min_size(pos2(x, y), vec2(square_size_inside, square_size_inside)), 
-                CornerRadius::same(1), 
-                instruction_color,
-                stroke,
-                StrokeKind::Inside
-            );
-
-            // moves next square's x 5 pixels to the left
-            x += square_size_outside;
-            if (x - response.rect.min.x) > window_width - square_size_inside { // if next square will overflow,
-                x = response.rect.min.x + x_margin; // set it's x back to the beginning
-                y += square_size_outside; // and move it down a row.
-
-                let label_color: Color32;
-                if ((y - response.rect.min.y)/square_size_outside).floor() % 4. == 0. {
-                    label_color = Color32::LIGHT_GRAY;
-                } else if ((y - response.rect.min.y)/square_size_outside).floor() % 2. == 0. {
-                    label_color = Color32::GRAY;
-                } else {
-                    label_color = Color32::DARK_GRAY;
+                    // the heatmap is drawn over a dimmed owner color, so activity stands out
+                    if let Some(heat_color) = app.heatmap.color(i) {
+                        instruction_color = Color32::from_rgb(
+                            (instruction_color.r() / 4).saturating_add(heat_color.r()),
+                            (instruction_color.g() / 4).saturating_add(heat_color.g()),
+                            (instruction_color.b() / 4).saturating_add(heat_color.b()),
+                        );
+                    }
+
+                    painter.rect(
+                        Rect::from_min_size(pos2(x, y), vec2(cell_size - gap, cell_size - gap)),
+                        CornerRadius::same(if gap > 0. { 1 } else { 0 }),
+                        instruction_color,
+                        stroke,
+                        StrokeKind::Inside
+                    );
                 }
+            }
 
-                if i != 7999 { painter.text(
-                    pos2(response.rect.min.x, y - 2.),
-                    Align2::LEFT_TOP,
-                    format!("{:04}", i + 1),
-                    FontId::monospace(label_font_size),
-                    label_color
-                ); }
-
-                if (y - response.rect.min.y) > window_height - square_size_inside { // if next row will overflow,
-                    // println!("boo");
-                    break; // stop rendering the squares.
+            if let Some(i) = hovered_cell {
+                hovered_text = format!("{:0label_digits$}  {}", i, display_instruction(app.mars.core[i], coresize));
+                if let Some(owner) = app.mars.owners[i] {
+                    hovered_text += &format!("  (team {}, cycle {})", owner.team, owner.cycle);
                 }
             }
-        }
 
-        painter.text(
-            pos2(response.rect.min.x, response.rect.min.y),
+            // ctrl + mouse wheel zooms around the cell under the mouse, which takes effect next frame
+            let zoom = ui.input(|input| input.zoom_delta());
+            if let Some(pos) = response.hover_pos().filter(|_| zoom != 1.) {
+                let new_cell_size = (cell_size * zoom).clamp(MIN_CELL_SIZE, MAX_CELL_SIZE);
+                let anchor = cell_at(pos).unwrap_or(((pos.y - origin.y) / cell_size) as usize * columns).min(coresize - 1);
+                let anchor_y = (anchor / columns) as f32 * cell_size; // where the anchor's row starts, from the top of the core
+                let new_anchor_y = (anchor / columns_for(cells_width, new_cell_size)) as f32 * new_cell_size;
+                // the same spot of the anchor's row stays under the mouse
+                let offset = new_anchor_y + (pos.y - origin.y - anchor_y) * new_cell_size / cell_size - (pos.y - origin.y - visible.min.y);
+                viewport.cell_size = new_cell_size;
+                viewport.scroll_to = Some(offset.max(0.));
+                viewport.fit = false;
+                ui.ctx().request_repaint();
+            }
+        });
+
+        ui.painter().text(
+            hover_rect.left_top(),
             Align2::LEFT_TOP,
             hovered_text,
             FontId::monospace(12.),
             Color32::LIGHT_GRAY
-        )
-
+        );
     });
 }
 
@@ -154,12 +251,345 @@ pub fn sim_manager(app: &mut EmarsApp, context: &Context) {
     Window::new("Simulation Manager")
     .show(context, |ui| {
         Grid::new("grid").show(ui, |ui| {
-            if ui.button("Step").clicked() { full_step(&mut app.core, app.coresize, &mut app.teams_process_queues, &mut app.turn); }
+            // the play thread's steps are added to the history as they come in, so stepping alongside it would mix two matches
+            if ui.add_enabled(!app.playing, Button::new("Step")).clicked() { app.mars.step(); }
             if ui.button("Play").clicked() { app.press_play(); }
             ui.end_row();
 
+            if ui.add_enabled(!app.playing && app.mars.can_step_back(), Button::new("Step Back")).clicked() { app.mars.step_back(); }
+            ui.end_row();
+
             ui.label("Delay per step in ms: ");
             ui.add(DragValue::new(&mut app.play_delay).range(1..=250));
+            ui.end_row();
+
+            // the timeline reaches back as far as the history goes, and forward to the furthest cycle reached
+            let mut cycle = app.mars.cycle;
+            ui.label("Timeline: ");
+            if ui.add(Slider::new(&mut cycle, app.mars.oldest_cycle()..=app.timeline_end)).changed() { app.seek(cycle); }
     });
+
+        ui.separator();
+        ui.label(format!("Cycle {} of {}", app.mars.cycle, app.mars.config.max_cycles));
+        ui.label(format!("Seed: {}", app.mars.config.seed));
+        ui.label(match app.mars.result() {
+            _ if app.mars.warrior_names.is_empty() => "No warriors loaded".to_string(),
+            MatchResult::Win(team) => format!("Team {team} ({}) wins!", app.mars.warrior_names[team as usize]),
+            MatchResult::Tie => "Tie!".to_string(),
+            MatchResult::Ongoing => "Match ongoing".to_string(),
+        });
+        if let Some(stop_reason) = &app.stop_reason {
+            ui.colored_label(Color32::ORANGE, format!("Stopped: {stop_reason}"));
+        }
+
+        ui.separator();
+        let mut edit: Option<usize> = None;
+        for (team, path) in app.warrior_paths.iter().enumerate() {
+            let text = match app.mars.warrior_names.get(team) {
+                Some(name) => {
+                    let status = match app.mars.death_cycles[team] {
+                        Some(cycle) => format!("died in cycle {cycle}"),
+                        None => format!("{} processes", app.mars.teams_process_queues[team].len()),
+                    };
+                    format!("Team {team}: {name} at {:04} ({status})", app.mars.start_positions[team])
+                }
+                None => format!("Team {team}: {path} (not loaded)"),
+            };
+            ui.horizontal(|ui| {
+                ui.colored_label(team_color(team as u8), text);
+                if ui.small_button("Edit").clicked() { edit = Some(team); }
+            });
+        }
+        if let Some(team) = edit { open_editor(app, team); }
+        ui.checkbox(&mut app.watching, "Reload when the warriors' files change");
+
+        ui.separator();
+        CollapsingHeader::new("Match settings (applied on restart)").show(ui, |ui| {
+            let coresize = app.config.coresize;
+            app.config.positions.resize(app.warrior_paths.len(), None);
+            Grid::new("settings_grid").show(ui, |ui| {
+                ui.label("Preset: ");
+                ComboBox::from_id_salt("preset")
+                .selected_text(app.preset.map_or("custom", |preset| preset.name()))
+                .show_ui(ui, |ui| {
+                    for preset in Preset::ALL {
+                        if ui.selectable_label(app.preset == Some(preset), preset.name()).clicked() {
+                            // the seed is kept so the same match can be replayed under another preset
+                            app.config = MarsConfig { seed: app.config.seed, ..preset.config() };
+                            app.preset = Some(preset);
+                        }
+                    }
+                });
+                ui.end_row();
+
+                ui.label("Coresize: ");
+                ui.label(format!("{}", app.config.coresize));
+                ui.end_row();
+
+                ui.label("Max processes: ");
+                ui.add(DragValue::new(&mut app.config.max_processes).range(1..=usize::MAX));
+                ui.end_row();
+
+                ui.label("Max cycles: ");
+                ui.add(DragValue::new(&mut app.config.max_cycles).range(1..=usize::MAX));
+                ui.end_row();
+
+                ui.label("Max length: ");
+                ui.add(DragValue::new(&mut app.config.max_length).range(1..=coresize));
+                ui.end_row();
+
+                ui.label("Min distance: ");
+                ui.add(DragValue::new(&mut app.config.min_distance).range(0..=coresize / 2));
+                ui.end_row();
+
+                ui.label("ICWS'88: ");
+                ui.checkbox(&mut app.config.icws88, "");
+                ui.end_row();
+
+                for (team, position) in app.config.positions.iter_mut().enumerate() {
+                    let mut fixed = position.is_some();
+                    ui.checkbox(&mut fixed, format!("Fix team {team} at: "));
+                    let mut address = position.unwrap_or(app.mars.start_positions.get(team).copied().unwrap_or(0));
+                    ui.add_enabled(fixed, DragValue::new(&mut address).range(0..=coresize - 1));
+                    *position = if fixed { Some(address) } else { None };
+                    ui.end_row();
+                }
+            });
+            if ui.button("Restart").clicked() { app.restart(); }
+        });
 });
-}
\ No newline at end of file
+}
+
+pub fn load_diagnostics(app: &mut EmarsApp, context: &Context) {
+    if app.diagnostics.is_empty() { return; }
+    Window::new("Load diagnostics")
+    .show(context, |ui| {
+        if app.diagnostics.iter().any(|diagnostic| diagnostic.severity == Severity::Error) {
+            ui.label("The match couldn't be loaded. Fix the warriors, then retry.");
+        } else {
+            ui.label("The match loaded with warnings.");
+        }
+        ui.separator();
+
+        Grid::new("diagnostics_grid").striped(true).show(ui, |ui| {
+            ui.strong("File");
+            ui.strong("Line");
+            ui.strong("Message");
+            ui.end_row();
+
+            let mut edit: Option<usize> = None;
+            for diagnostic in &app.diagnostics {
+                let color = match diagnostic.severity {
+                    Severity::Warning => Color32::YELLOW,
+                    Severity::Error => Color32::RED,
+                };
+                ui.label(diagnostic.path.clone().unwrap_or_default());
+                ui.label(diagnostic.line.map_or(String::new(), |line| line.to_string()));
+                ui.colored_label(color, &diagnostic.message);
+                let team = app.warrior_paths.iter().position(|path| Some(path) == diagnostic.path.as_ref());
+                if team.is_some() && ui.small_button("Edit").clicked() { edit = team; }
+                ui.end_row();
+            }
+            if let Some(team) = edit { open_editor(app, team); }
+        });
+
+        ui.separator();
+        ui.horizontal(|ui| {
+            if ui.button("Retry").clicked() { app.restart(); }
+            if ui.button("Dismiss").clicked() { app.diagnostics.clear(); }
+        });
+    });
+}
+
+pub fn breakpoints_window(app: &mut EmarsApp, context: &Context) {
+    Window::new("Breakpoints and watchpoints")
+    .default_open(false)
+    .show(context, |ui| {
+        let coresize = app.mars.config.coresize;
+        let mut changed = false;
+        let mut removed: Option<usize> = None;
+        for (i, breakpoint) in app.breakpoints.iter().enumerate() {
+            ui.horizontal(|ui| {
+                ui.label(breakpoint.to_string());
+                if ui.small_button("Remove").clicked() { removed = Some(i); }
+            });
+        }
+        if let Some(i) = removed { app.breakpoints.remove(i); changed = true; }
+        if app.breakpoints.is_empty() { ui.label("No breakpoints set"); }
+        ui.separator();
+
+        let new_breakpoint = &mut app.new_breakpoint;
+        Grid::new("breakpoint_grid").show(ui, |ui| {
+            ui.label("Team: ");
+            ComboBox::from_id_salt("breakpoint_team")
+            .selected_text(new_breakpoint.team.map_or("Any team".to_string(), |team| format!("Team {team}")))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut new_breakpoint.team, None, "Any team");
+                for team in 0..app.warrior_paths.len() {
+                    ui.selectable_value(&mut new_breakpoint.team, Some(team as u8), format!("Team {team}"));
+                }
+            });
+            ui.end_row();
+
+            ui.label("Stop before: ");
+            ComboBox::from_id_salt("breakpoint_kind")
+            .selected_text(match new_breakpoint.kind {
+                BreakpointKind::Address(_) => "an address",
+                BreakpointKind::Range(_, _) => "an address range",
+                BreakpointKind::Opcode(_) => "an opcode",
+            })
+            .show_ui(ui, |ui| {
+                let kind = &mut new_breakpoint.kind;
+                if ui.selectable_label(matches!(kind, BreakpointKind::Address(_)), "an address").clicked() { *kind = BreakpointKind::Address(0); }
+                if ui.selectable_label(matches!(kind, BreakpointKind::Range(_, _)), "an address range").clicked() { *kind = BreakpointKind::Range(0, 0); }
+                if ui.selectable_label(matches!(kind, BreakpointKind::Opcode(_)), "an opcode").clicked() { *kind = BreakpointKind::Opcode(Opcode::Dat); }
+            });
+            ui.end_row();
+
+            match &mut new_breakpoint.kind {
+                BreakpointKind::Address(address) => {
+                    ui.label("Address: ");
+                    ui.add(DragValue::new(address).range(0..=coresize - 1));
+                }
+                BreakpointKind::Range(start, end) => {
+                    ui.label("From/to: ");
+                    ui.horizontal(|ui| {
+                        ui.add(DragValue::new(start).range(0..=coresize - 1));
+                        ui.add(DragValue::new(end).range(0..=coresize - 1));
+                    });
+                }
+                BreakpointKind::Opcode(opcode) => {
+                    ui.label("Opcode: ");
+                    ComboBox::from_id_salt("breakpoint_opcode")
+                    .selected_text(opcode.to_string())
+                    .show_ui(ui, |ui| {
+                        for other in Opcode::ALL {
+                            ui.selectable_value(opcode, other, other.to_string());
+                        }
+                    });
+                }
+            }
+            ui.end_row();
+        });
+        if ui.button("Add").clicked() { app.breakpoints.push(app.new_breakpoint); changed = true; }
+
+        ui.separator();
+        ui.strong("Write watchpoints");
+        let mut removed: Option<usize> = None;
+        for (i, watchpoint) in app.watchpoints.iter().enumerate() {
+            ui.horizontal(|ui| {
+                ui.label(watchpoint.to_string());
+                if ui.small_button("Remove").clicked() { removed = Some(i); }
+            });
+        }
+        if let Some(i) = removed { app.watchpoints.remove(i); changed = true; }
+        if app.watchpoints.is_empty() { ui.label("No watchpoints set"); }
+        ui.horizontal(|ui| {
+            ui.label("Stop after writes to: ");
+            ui.add(DragValue::new(&mut app.new_watchpoint.start).range(0..=coresize - 1));
+            ui.label("to");
+            ui.add(DragValue::new(&mut app.new_watchpoint.end).range(0..=coresize - 1));
+        });
+        if ui.button("Add watchpoint").clicked() { app.watchpoints.push(app.new_watchpoint); changed = true; }
+
+        // the play thread has its own copy of the breakpoints and watchpoints, so it's restarted with the new ones
+        if changed && app.playing {
+            app.press_play();
+            app.press_play();
+        }
+    });
+}
+
+pub fn process_queues(app: &mut EmarsApp, context: &Context) {
+    Window::new("Process Queues")
+    .default_open(false)
+    .show(context, |ui| {
+        let mars = &app.mars;
+        let total: usize = mars.teams_process_queues.iter().map(|process_queue| process_queue.len()).sum();
+        ui.label(format!("{total} processes in total"));
+        // teams take turns in order, so the next team to run is the first living one from turn onwards
+        let next_team = (mars.turn..mars.teams_process_queues.len()).find(|team| mars.is_alive(*team));
+
+        for (team, process_queue) in mars.teams_process_queues.iter().enumerate() {
+            let next = if next_team == Some(team) { ", runs next" } else { "" };
+            let title = RichText::new(format!("Team {team}: {} ({} processes{next})", mars.warrior_names[team], process_queue.len()))
+                .color(team_color(team as u8));
+            CollapsingHeader::new(title).id_salt(("process_queue", team)).default_open(true).show(ui, |ui| {
+                let row_height = ui.text_style_height(&TextStyle::Monospace);
+                ScrollArea::vertical().id_salt(("process_queue_scroll", team)).max_height(200.).show_rows(ui, row_height, process_queue.len(), |ui, rows| {
+                    for i in rows {
+                        let process = process_queue[i];
+                        let text = format!("{i:>5}  {:04}  {}", process.pointer, display_instruction(mars.core[process.pointer], mars.config.coresize));
+                        // the first process in the queue is the one this team steps next
+                        if i == 0 {
+                            ui.label(RichText::new(text).monospace().color(team_color(team as u8)));
+                        } else {
+                            ui.label(RichText::new(text).monospace());
+                        }
+                    }
+                });
+            });
+        }
+    });
+}
+
+pub fn cell_details(app: &mut EmarsApp, context: &Context) {
+    // a new match can have a smaller core than the pinned cell
+    let Some(address) = app.pinned_cell.filter(|address| *address < app.mars.config.coresize) else {
+        app.pinned_cell = None;
+        return;
+    };
+    let mars = &app.mars;
+    let coresize = mars.config.coresize;
+    let mut open = true;
+    Window::new("Cell Details")
+    .open(&mut open)
+    .show(context, |ui| {
+        Grid::new("cell_details").num_columns(2).show(ui, |ui| {
+            ui.label("Address: ");
+            ui.monospace(format!("{address:04}"));
+            ui.end_row();
+
+            ui.label("Instruction: ");
+            ui.monospace(display_instruction(mars.core[address], coresize));
+            ui.end_row();
+
+            ui.label("Owner: ");
+            match mars.owners[address] {
+                Some(owner) => ui.colored_label(team_color(owner.team), format!("Team {}: {}", owner.team, mars.warrior_names[owner.team as usize])),
+                None => ui.label("None"),
+            };
+            ui.end_row();
+
+            ui.label("Last written: ");
+            match mars.owners[address] {
+                Some(Owner { writer: Some(writer), cycle, .. }) => ui.label(format!("by the instruction at {writer:04} in cycle {cycle}")),
+                Some(_) => ui.label("when the warrior was loaded"),
+                None => ui.label("never"),
+            };
+            ui.end_row();
+
+            // where the fields would point if a process executed this cell right now
+            let (a_target, b_target) = mars.targets(address);
+            ui.label("A target: ");
+            ui.monospace(format!("{a_target:04}  {}", display_instruction(mars.core[a_target], coresize)));
+            ui.end_row();
+
+            ui.label("B target: ");
+            ui.monospace(format!("{b_target:04}  {}", display_instruction(mars.core[b_target], coresize)));
+            ui.end_row();
+        });
+
+        ui.separator();
+        let mut pointing = 0;
+        for (team, process_queue) in mars.teams_process_queues.iter().enumerate() {
+            for (i, process) in process_queue.iter().enumerate().filter(|(_, process)| process.pointer == address) {
+                let next = if i == 0 { ", runs next for its team" } else { "" };
+                ui.colored_label(team_color(process.team), format!("Team {team}: {}, process {i} of {}{next}", mars.warrior_names[team], process_queue.len()));
+                pointing += 1;
+            }
+        }
+        if pointing == 0 { ui.label("No processes point here"); }
+    });
+    if !open { app.pinned_cell = None; }
+}
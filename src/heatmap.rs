@@ -0,0 +1,75 @@
+use eframe::egui;
+use egui::Color32;
+use emars::{Activity, Mars};
+
+const DECAY: f32 = 0.97; // how much of a cell's heat is left after each cycle
+const FULL_HEAT: f32 = 4.; // the heat drawn at full brightness
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum HeatmapMode {
+    Off,
+    All, // writes in red, executions in green and reads in blue
+    Reads,
+    Writes,
+    Executions,
+}
+
+impl HeatmapMode {
+    pub const ALL: [HeatmapMode; 5] = [HeatmapMode::Off, HeatmapMode::All, HeatmapMode::Reads, HeatmapMode::Writes, HeatmapMode::Executions];
+
+    pub fn name(&self) -> &'static str {
+        return match self {
+            HeatmapMode::Off => "Off",
+            HeatmapMode::All => "All",
+            HeatmapMode::Reads => "Reads",
+            HeatmapMode::Writes => "Writes",
+            HeatmapMode::Executions => "Executions",
+        };
+    }
+}
+
+// recent activity of each cell, which fades as the match goes on
+pub(crate) struct Heatmap {
+    pub mode: HeatmapMode,
+    heat: Vec<[f32; 3]>, // the recent reads, writes and executions of each cell
+    activity: Vec<Activity>, // the activity counters as of the last update
+    cycle: usize, // the cycle of the last update
+}
+
+impl Heatmap {
+    pub fn new() -> Heatmap {
+        return Heatmap { mode: HeatmapMode::Off, heat: Vec::new(), activity: Vec::new(), cycle: 0 };
+    }
+
+    pub fn update(&mut self, mars: &Mars) { // adds the accesses since the last update and decays the rest
+        // a new match or a step back starts the heat over
+        if mars.cycle < self.cycle || self.activity.len() != mars.activity.len() {
+            self.heat = vec![[0.; 3]; mars.activity.len()];
+            self.activity = mars.activity.clone();
+            self.cycle = mars.cycle;
+            return;
+        }
+        let decay = DECAY.powi((mars.cycle - self.cycle) as i32);
+        for (i, activity) in mars.activity.iter().enumerate() {
+            let old = self.activity[i];
+            let heat = &mut self.heat[i];
+            heat[0] = heat[0] * decay + activity.reads.saturating_sub(old.reads) as f32;
+            heat[1] = heat[1] * decay + activity.writes.saturating_sub(old.writes) as f32;
+            heat[2] = heat[2] * decay + activity.executions.saturating_sub(old.executions) as f32;
+        }
+        self.activity.clone_from(&mars.activity);
+        self.cycle = mars.cycle;
+    }
+
+    pub fn color(&self, cell: usize) -> Option<Color32> { // the overlay color of a cell, if the heatmap is on
+        let [reads, writes, executions] = self.heat.get(cell)?.map(|heat| (heat / FULL_HEAT).min(1.));
+        let scale = |heat: f32| (heat * 255.) as u8;
+        return match self.mode {
+            HeatmapMode::Off => None,
+            HeatmapMode::All => Some(Color32::from_rgb(scale(writes), scale(executions), scale(reads))),
+            HeatmapMode::Reads => Some(Color32::from_rgb(0, scale(reads) / 2, scale(reads))),
+            HeatmapMode::Writes => Some(Color32::from_rgb(scale(writes), scale(writes) / 4, 0)),
+            HeatmapMode::Executions => Some(Color32::from_rgb(scale(executions) / 2, scale(executions), 0)),
+        };
+    }
+}
@@ -0,0 +1,9 @@
+#![allow(clippy::needless_return)]
+
+pub mod sim;
+pub mod mars;
+pub mod preset;
+
+pub use mars::{Activity, CycleStatistics, Mars, MarsConfig, MatchResult, Owner, StepRecord};
+pub use preset::Preset;
+pub use sim::{Diagnostic, LoadError, Severity};
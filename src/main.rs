@@ -1,29 +1,61 @@
 #![allow(unused_mut)]
+#![allow(clippy::needless_return)]
 
 use std::env::args;
-use std::time::{Duration, Instant};
+use std::fmt::Display;
+use std::str::FromStr;
+#[cfg(feature = "gui")]
+use std::time::{Duration, Instant, SystemTime};
+#[cfg(feature = "gui")]
 use std::sync::mpsc::{Sender, Receiver, channel};
-use crate::sim::{Instruction, Field, Process};
-use corewars_core::load_file::{Opcode, AddressMode, Modifier};
-use std::collections::VecDeque;
+#[cfg(feature = "gui")]
+use emars::{Diagnostic, Mars};
+use emars::{MarsConfig, Preset};
 
-mod sim;
+mod batch;
+// everything below is the window, which headless mode doesn't need
+#[cfg(feature = "gui")]
 mod gui;
+#[cfg(feature = "gui")]
+mod play;
+#[cfg(feature = "gui")]
+mod editor;
+#[cfg(feature = "gui")]
+mod breakpoint;
+#[cfg(feature = "gui")]
+mod heatmap;
+#[cfg(feature = "gui")]
+mod statistics;
+#[cfg(feature = "gui")]
+mod disassembly;
 
+#[cfg(feature = "gui")]
 pub(crate) struct EmarsApp {
-    core: Vec<Instruction>, // the core.
-    coresize: usize, // the size of the core, usually 8000 cells
-    default_instruction: Instruction, // the default instruction for the core, usually DAT.F #0, #0
-    core_view_size: usize, // the visual size of the core view
-    teams_process_queues: Vec<VecDeque<Process>>, // each teams process queue in order
-    turn: usize, // which teams turn it is
+    mars: Mars, // the simulator, holding the core, process queues and match config
+    warrior_paths: Vec<String>, // the filepaths of every warrior in the match, in team order
+    config: MarsConfig, // the match config used the next time the match is restarted
+    preset: Option<Preset>, // the preset config was last set from
+    diagnostics: Vec<Diagnostic>, // the errors and warnings from the last time the warriors were loaded
+    editors: Vec<editor::WarriorEditor>, // the editor window of each warrior, in team order
+    watching: bool, // whether to reload the match when a warrior's file changes
+    modified_times: Vec<Option<SystemTime>>, // when each warrior's file was last modified, as of the last load
+    last_watch: Instant, // the last time the files were checked for changes
+    timeline_end: usize, // the furthest cycle this match has reached, which the timeline can be scrubbed up to
+    core_viewport: gui::CoreViewport, // how far the core view is zoomed in
+    pinned_cell: Option<usize>, // the cell shown in the Cell Details window, once one is clicked
+    disassembly: disassembly::Disassembly, // the state of the Disassembly window
     playing: bool, // whether the simulation is playing
     play_delay: usize, // the number of milliseconds per step during play
     last_step: Instant, //  the time since the last step during play
-    state_sender: Sender<(Vec<Instruction>, Vec<VecDeque<Process>>)>,
-    state_receiver: Receiver<(Vec<Instruction>, Vec<VecDeque<Process>>)>,
-    play_step_count: usize, // number of steps since play started
-    play_step_limit: usize, // number of steps until tie is declared
+    breakpoints: Vec<breakpoint::Breakpoint>, // stop play before any of these are executed
+    new_breakpoint: breakpoint::Breakpoint, // the breakpoint being set up in the Breakpoints window
+    watchpoints: Vec<breakpoint::Watchpoint>, // stop play after any of these addresses are written to
+    new_watchpoint: breakpoint::Watchpoint, // the watchpoint being set up in the Breakpoints window
+    stop_reason: Option<String>, // why play was last stopped by a breakpoint or watchpoint
+    heatmap: heatmap::Heatmap, // the recent activity of each cell, drawn over the core view
+    statistics: statistics::Statistics, // the statistics of every cycle of the match so far
+    state_sender: Sender<play::PlayUpdate>,
+    state_receiver: Receiver<play::PlayUpdate>,
 }
 
 // fn print_core(core: &Vec<Instruction>) {
@@ -32,64 +64,209 @@ pub(crate) struct EmarsApp {
 //     }
 // }
 
-fn load_core(args: Vec<String>, default_instruction: Instruction) -> (usize, (Vec<Instruction>, Vec<VecDeque<Process>>)) {
-    let coresize: usize;
-    match args.len() {
-        ..=2 => panic!("Not enough arguments"),
-        3 => coresize = 8000,
-        4 => coresize = match args[3].parse::<usize>() { Ok(n) => n, Err(e) => panic!("Could not parse coresize argument: {e}")},
-        _ => panic!("Too many arguments")
+struct Options {
+    warrior_paths: Vec<String>, // the filepaths of every warrior in the match, in team order
+    coresize: Option<usize>, // the size of the core, 8000 or the preset's if not given
+    preset: Option<Preset>, // the hill settings to start from, which the other options override
+    headless: bool, // whether to run a batch of rounds without opening the window
+    rounds: usize, // the number of rounds to run in headless mode
+    max_processes: Option<usize>, // the process limit for each team, 8000 if not given
+    max_cycles: Option<usize>, // the number of cycles until a tie, coresize * 10 if not given
+    max_length: Option<usize>, // the longest a warrior can be, 100 if not given
+    pspace_size: Option<usize>, // the size of each warrior's P-space, coresize / 16 if not given
+    read_limit: Option<usize>, // the read distance limit, coresize if not given
+    write_limit: Option<usize>, // the write distance limit, coresize if not given
+    seed: Option<u64>, // the seed for warrior placement, random if not given
+    min_distance: Option<usize>, // the least distance between warriors, coresize / 80 if not given
+    positions: Vec<Option<usize>>, // fixed start addresses by team
+    icws88: bool, // whether to validate and run the warriors as ICWS'88
+    #[cfg_attr(not(feature = "gui"), allow(dead_code))]
+    watch: bool, // whether to reload the match when a warrior's file changes
+}
+
+fn parse_position(value: Option<String>, positions: &mut Vec<Option<usize>>) { // parses a "team:address" position
+    let value: String = parse_flag_value("--position", value);
+    let (team, address) = match value.split_once(':') { Some(position) => position, None => panic!("--position must look like team:address") };
+    let team: usize = parse_flag_value("--position team", Some(team.to_string()));
+    let address: usize = parse_flag_value("--position address", Some(address.to_string()));
+    if positions.len() <= team { positions.resize(team + 1, None); }
+    positions[team] = Some(address);
+}
+
+fn parse_preset(value: Option<String>) -> Preset {
+    let name: String = parse_flag_value("--preset", value);
+    match Preset::from_name(&name) {
+        Some(preset) => preset,
+        None => panic!("Unknown preset {name}, the presets are: {}", Preset::ALL.map(|preset| preset.name()).join(", "))
+    }
+}
+
+fn parse_flag_value<T: FromStr>(flag: &str, value: Option<String>) -> T where T::Err: Display {
+    match value {
+        Some(value) => match value.parse::<T>() { Ok(n) => n, Err(e) => panic!("Could not parse {flag} argument: {e}")},
+        None => panic!("Missing value for {flag}")
+    }
+}
+
+fn parse_args(args: Vec<String>) -> Options {
+    let mut positional: Vec<String> = Vec::new();
+    let mut headless = false;
+    let mut rounds: usize = 1;
+    let mut preset: Option<Preset> = None;
+    let mut max_processes: Option<usize> = None;
+    let mut max_cycles: Option<usize> = None;
+    let mut max_length: Option<usize> = None;
+    let mut pspace_size: Option<usize> = None;
+    let mut read_limit: Option<usize> = None;
+    let mut write_limit: Option<usize> = None;
+    let mut seed: Option<u64> = None;
+    let mut min_distance: Option<usize> = None;
+    let mut positions: Vec<Option<usize>> = Vec::new();
+    let mut icws88 = false;
+    let mut watch = false;
+    let mut args = args.into_iter().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--headless" => headless = true,
+            "--icws88" => icws88 = true,
+            "--watch" => watch = true,
+            "--rounds" => rounds = parse_flag_value("--rounds", args.next()),
+            "--preset" => preset = Some(parse_preset(args.next())),
+            "--max-processes" => max_processes = Some(parse_flag_value("--max-processes", args.next())),
+            "--max-cycles" => max_cycles = Some(parse_flag_value("--max-cycles", args.next())),
+            "--max-length" => max_length = Some(parse_flag_value("--max-length", args.next())),
+            "--pspace-size" => pspace_size = Some(parse_flag_value("--pspace-size", args.next())),
+            "--read-limit" => read_limit = Some(parse_flag_value("--read-limit", args.next())),
+            "--write-limit" => write_limit = Some(parse_flag_value("--write-limit", args.next())),
+            "--seed" => seed = Some(parse_flag_value("--seed", args.next())),
+            "--min-distance" => min_distance = Some(parse_flag_value("--min-distance", args.next())),
+            "--position" => parse_position(args.next(), &mut positions),
+            _ => positional.push(arg)
+        }
+    }
+
+    // a trailing number is the coresize, everything else is a warrior
+    let mut coresize: Option<usize> = None;
+    if positional.last().is_some_and(|arg| arg.parse::<usize>().is_ok()) {
+        coresize = Some(parse_flag_value("coresize", positional.pop()));
+    }
+    if positional.len() < 2 { panic!("Not enough arguments") }
+    return Options { warrior_paths: positional, coresize, preset, headless, rounds, max_processes, max_cycles, max_length, pspace_size, read_limit, write_limit, seed, min_distance, positions, icws88, watch };
+}
+
+fn build_config(options: &Options) -> MarsConfig { // starts from the preset or the defaults, then applies every option given
+    let mut config = match options.preset {
+        Some(preset) => preset.config(),
+        None => MarsConfig::new(options.coresize.unwrap_or(8000)),
+    };
+    if let Some(coresize) = options.coresize {
+        config.coresize = coresize;
+        config.read_limit = config.read_limit.min(coresize);
+        config.write_limit = config.write_limit.min(coresize);
     }
-    return (coresize, sim::init(args[1].clone(), args[2].clone(), coresize, default_instruction));
+    if let Some(max_processes) = options.max_processes { config.max_processes = max_processes; }
+    if let Some(max_cycles) = options.max_cycles { config.max_cycles = max_cycles; }
+    if let Some(max_length) = options.max_length { config.max_length = max_length; }
+    if let Some(pspace_size) = options.pspace_size { config.pspace_size = pspace_size; }
+    if let Some(read_limit) = options.read_limit { config.read_limit = read_limit; }
+    if let Some(write_limit) = options.write_limit { config.write_limit = write_limit; }
+    if let Some(seed) = options.seed { config.seed = seed; }
+    if let Some(min_distance) = options.min_distance { config.min_distance = min_distance; }
+    config.positions = options.positions.clone();
+    if options.icws88 { config.icws88 = true; }
+    return config;
 }
 
 // const FRAMETIME: f64 = 1./60.;
+#[cfg(feature = "gui")]
 impl eframe::App for EmarsApp {
     fn update(&mut self, context: &egui::Context, _: &mut eframe::Frame) {
-        match self.state_receiver.try_recv() {
-            Ok((core, queues)) => { self.core = core; self.teams_process_queues = queues; },
-            Err(_) => {}
+        while let Ok(update) = self.state_receiver.try_recv() {
+            let (stop_reason, ended) = (update.stop_reason.clone(), update.ended);
+            self.receive_update(update);
+            if stop_reason.is_some() || ended {
+                self.press_play();
+                self.stop_reason = stop_reason;
+                break;
+            }
         }
+        self.timeline_end = self.timeline_end.max(self.mars.cycle);
+        self.heatmap.update(&self.mars);
+        self.statistics.update(&self.mars);
+        self.disassembly.update(&self.mars);
         gui::core_view(self, context);
+        gui::cell_details(self, context);
         gui::sim_manager(self, context);
+        gui::load_diagnostics(self, context);
+        editor::editor_windows(self, context);
+        gui::breakpoints_window(self, context);
+        gui::process_queues(self, context);
+        statistics::statistics_window(self, context);
+        disassembly::disassembly_window(self, context);
+        self.watch_files();
         if self.playing { context.request_repaint_after(Duration::from_millis(10)) };
+        if self.watching { context.request_repaint_after(play::WATCH_INTERVAL) };
     }
 }
 
 fn main() {
     let args: Vec<String> = args().collect();
-    let default_instruction: Instruction = Instruction {
-        opcode: Opcode::Dat,
-        modifier: Modifier::F, 
-        field_a: Field {
-            address_mode: AddressMode::Immediate,
-            value: 0,
-        },
-        field_b: Field {
-            address_mode: AddressMode::Immediate,
-            value: 0,
-        }
+    let options = parse_args(args);
+    let config = build_config(&options);
+    if options.headless {
+        batch::run_batch(&options.warrior_paths, config, options.rounds);
+        return;
+    }
+    run_window(options, config);
+}
+
+#[cfg(not(feature = "gui"))]
+fn run_window(_: Options, _: MarsConfig) {
+    eprintln!("error: eMARS was built without the gui feature, so only --headless works");
+    std::process::exit(1);
+}
+
+#[cfg(feature = "gui")]
+#[allow(clippy::assertions_on_constants)]
+fn run_window(options: Options, config: MarsConfig) {
+    // nothing in the window can change the core size or the limits, so a bad config can't be fixed from there
+    if let Err(message) = config.check() { batch::exit_with(emars::LoadError::from(message)); }
+    let modified_times = play::modified_times(&options.warrior_paths);
+    let (mut mars, diagnostics) = match Mars::load(config.clone(), &options.warrior_paths) {
+        Ok((mars, warnings)) => (mars, warnings),
+        // the window still opens with an empty core, so the warrior files can be fixed and reloaded from there
+        Err(error) => (Mars::empty(config.clone()), error.diagnostics),
     };
-    
-    let (mut coresize, (mut core, mut teams_process_queue)) = load_core(args, default_instruction);
-    let (play_sender, play_receiver) = channel::<(Vec<Instruction>, Vec<VecDeque<Process>>)>();
-    let turn = 0;
+    mars.history_length = play::HISTORY_LENGTH;
+    let (play_sender, play_receiver) = channel::<play::PlayUpdate>();
 
-    let core_view_size = 2;
+    let editors = options.warrior_paths.iter().map(|_| editor::WarriorEditor::default()).collect();
     let app = EmarsApp {
-        core,
-        coresize,
-        default_instruction,
-        core_view_size,
-        teams_process_queues: teams_process_queue,
-        turn,
+        mars,
+        warrior_paths: options.warrior_paths,
+        config,
+        preset: options.preset,
+        diagnostics,
+        editors,
+        watching: options.watch,
+        modified_times,
+        last_watch: Instant::now(),
+        timeline_end: 0,
+        breakpoints: Vec::new(),
+        new_breakpoint: breakpoint::Breakpoint::default(),
+        watchpoints: Vec::new(),
+        new_watchpoint: breakpoint::Watchpoint::default(),
+        stop_reason: None,
+        heatmap: heatmap::Heatmap::new(),
+        statistics: statistics::Statistics::new(),
+        core_viewport: gui::CoreViewport::new(),
+        pinned_cell: None,
+        disassembly: disassembly::Disassembly::new(),
         playing: false,
         play_delay: 1,
         last_step: Instant::now(),
         state_sender: play_sender,
         state_receiver: play_receiver,
-        play_step_count: 0,
-        play_step_limit: coresize * 10,
     };
 
     match eframe::run_native(
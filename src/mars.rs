@@ -0,0 +1,497 @@
+use std::collections::VecDeque;
+use corewars_core::load_file::Warrior;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use crate::sim::{Diagnostic, Instruction, LoadError, Opcode, Process, Placement, Severity, DEFAULT_INSTRUCTION, check_warriors, parse_warriors, place_warriors};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum MatchResult {
+    Win(u8), // only this team has processes left
+    Tie, // the cycle limit was reached with more than one team alive
+    Ongoing, // more than one team is alive and the cycle limit hasn't been reached
+}
+
+#[derive(Clone)]
+pub struct MarsConfig {
+    pub coresize: usize, // the size of the core, usually 8000 cells
+    pub max_cycles: usize, // number of cycles until a tie is declared
+    pub max_length: usize, // the most instructions a warrior can have
+    pub max_processes: usize, // the most processes a single team can have, SPL does nothing past this
+    pub pspace_size: usize, // the number of cells in each warrior's private P-space
+    pub read_limit: usize, // reads are folded into a window of this many cells around the process
+    pub write_limit: usize, // writes are folded into a window of this many cells around the process
+    pub seed: u64, // seeds the warrior placement, so a match (and every round after it) can be replayed
+    pub min_distance: usize, // the least number of cells between the starts of any two warriors
+    pub positions: Vec<Option<usize>>, // fixed start addresses by team, the rest are placed randomly
+    pub allowed_opcodes: Vec<Opcode>, // warriors using any other opcode are rejected when loading
+    pub default_instruction: Instruction, // the default instruction for the core, usually DAT.F #0, #0
+    pub icws88: bool, // whether warriors must be valid ICWS'88 and run with its CMP semantics
+}
+
+impl MarsConfig {
+    pub fn new(coresize: usize) -> MarsConfig {
+        return MarsConfig {
+            coresize,
+            max_cycles: coresize * 10,
+            max_length: 100,
+            max_processes: 8000,
+            pspace_size: (coresize / 16).max(1),
+            read_limit: coresize,
+            write_limit: coresize,
+            seed: rand::rng().random(),
+            min_distance: coresize / 80,
+            positions: Vec::new(),
+            allowed_opcodes: Opcode::ALL.to_vec(),
+            default_instruction: DEFAULT_INSTRUCTION,
+            icws88: false,
+        };
+    }
+
+    pub fn check(&self) -> Result<(), String> { // why a match can't be run with this config, if it can't
+        if self.coresize == 0 { return Err("The coresize must be at least 1".to_string()); }
+        if self.max_processes == 0 { return Err("The process limit must be at least 1".to_string()); }
+        if self.max_cycles == 0 { return Err("The cycle limit must be at least 1".to_string()); }
+        if self.pspace_size == 0 { return Err("The P-space size must be at least 1".to_string()); }
+        // fold divides by the limits, and a window bigger than the core doesn't mean anything
+        for (name, limit) in [("read", self.read_limit), ("write", self.write_limit)] {
+            if limit == 0 || limit > self.coresize { return Err(format!("The {name} limit must be between 1 and the coresize, not {limit}")); }
+        }
+        return Ok(());
+    }
+}
+
+// the team that last wrote to a cell, and when and from where
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Owner {
+    pub team: u8,
+    pub cycle: usize, // the cycle of the write, 0 for cells a warrior was loaded into
+    pub writer: Option<usize>, // the address of the instruction that wrote, None for cells a warrior was loaded into
+}
+
+// how often a cell has been accessed this round
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub struct Activity {
+    pub reads: usize, // including reads of indirect pointers
+    pub writes: usize, // including increments and decrements of indirect pointers
+    pub executions: usize,
+}
+
+// a summary of the match after a cycle, for following how a fight goes
+#[derive(Clone, PartialEq, Debug)]
+pub struct CycleStatistics {
+    pub cycle: usize,
+    pub processes: Vec<usize>, // each team's number of processes
+    pub owned_cells: Vec<usize>, // the number of cells each team last wrote to
+    pub opcodes: [usize; Opcode::ALL.len()], // how often each opcode in Opcode::ALL was executed in the last cycle
+}
+
+// what one process's step changed, so it can be undone
+#[derive(Clone, Copy)]
+pub struct StepRecord {
+    pub cycle: usize, // the cycle the step was taken in
+    pub process: Process, // the process that was stepped, as it was before the step
+    pub instruction: Instruction, // the instruction the process executed
+    pub queue_length: usize, // the length of its team's process queue before the step
+    pub saved_cells: [(usize, Instruction); 3], // every cell the step could have written to and what it held, in the order they were saved
+    pub saved_owners: [Option<Owner>; 3], // the owner of each of saved_cells before the step
+    pub written: [bool; 3], // which of saved_cells the step actually wrote to
+    pub read_cells: [Option<usize>; 4], // the cells the step read from
+    pub saved_pspace_cell: Option<(usize, usize)>, // the P-space cell STP wrote to and what it held
+}
+
+impl StepRecord {
+    pub fn written_addresses(&self) -> impl Iterator<Item = usize> + '_ { // every address the step wrote to, which can repeat
+        return self.saved_cells.iter().zip(self.written).filter(|(_, written)| *written).map(|((address, _), _)| *address);
+    }
+}
+
+#[derive(Clone)]
+pub struct Mars {
+    pub core: Vec<Instruction>, // the core.
+    pub owners: Vec<Option<Owner>>, // the team that last wrote to each cell of the core, if any has
+    owned_cells: Vec<usize>, // the number of cells in owners each team has, kept up to date so statistics doesn't count them every cycle
+    pub activity: Vec<Activity>, // how often each cell of the core has been read, written and executed
+    pub teams_process_queues: Vec<VecDeque<Process>>, // each teams process queue in order, indexed by team and empty once it has died
+    pub turn: usize, // which teams turn it is
+    pub config: MarsConfig,
+    pub cycle: usize, // number of full steps taken since the match started
+    pub warrior_names: Vec<String>, // the name of each team's warrior
+    pub pspaces: Vec<Vec<usize>>, // each team's P-space, kept between rounds
+    pub death_cycles: Vec<Option<usize>>, // the cycle each team lost its last process in, if it has
+    pub start_positions: Vec<usize>, // the address each team's warrior was loaded at this round
+    pub last_steps: Vec<StepRecord>, // what each team's step did in the last cycle
+    pub history_length: usize, // the number of cycles that can be stepped back through, 0 to not keep any history
+    history: VecDeque<StepRecord>, // the most recent steps, oldest first
+    rng: StdRng, // places the warriors each round, seeded by config.seed
+}
+
+fn count_owned_cells(owners: &[Option<Owner>], teams: usize) -> Vec<usize> { // the number of cells each team owns
+    let mut owned_cells = vec![0; teams];
+    for owner in owners.iter().flatten() {
+        owned_cells[owner.team as usize] += 1;
+    }
+    return owned_cells;
+}
+
+impl Mars {
+    pub fn new(config: MarsConfig, warriors: &[Warrior]) -> Result<Mars, LoadError> {
+        config.check()?;
+        let mut rng = StdRng::seed_from_u64(config.seed);
+        let Placement { core, owners, teams_process_queues, start_positions } = place_warriors(warriors, &config, &mut rng)?;
+        let warrior_names = warriors.iter().enumerate()
+            .map(|(team, warrior)| warrior.metadata.name.clone().unwrap_or_else(|| format!("Warrior {team}")))
+            .collect();
+        // P-space cell 0 holds the result of the last round, which is -1 before the first one
+        let mut pspace = vec![0; config.pspace_size];
+        pspace[0] = config.coresize - 1;
+        let pspaces = vec![pspace; warriors.len()];
+        let death_cycles = vec![None; warriors.len()];
+        let activity = vec![Activity::default(); config.coresize];
+        let owned_cells = count_owned_cells(&owners, warriors.len());
+        return Ok(Mars { core, owners, owned_cells, activity, teams_process_queues, turn: 0, config, cycle: 0, warrior_names, pspaces, death_cycles, start_positions, last_steps: Vec::new(), history_length: 0, history: VecDeque::new(), rng });
+    }
+
+    pub fn next_round(&mut self, warriors: &[Warrior]) -> Result<(), LoadError> { // re-places the warriors for a new round, keeping their P-spaces
+        self.config.check()?;
+        // the last round's result is 0 for a loss, otherwise the number of warriors still alive
+        let survivors = self.living_teams();
+        for (team, pspace) in self.pspaces.iter_mut().enumerate() {
+            pspace[0] = if self.death_cycles[team].is_none() { survivors } else { 0 };
+        }
+        let placement = place_warriors(warriors, &self.config, &mut self.rng)?;
+        self.core = placement.core;
+        self.owners = placement.owners;
+        self.owned_cells = count_owned_cells(&self.owners, warriors.len());
+        self.activity = vec![Activity::default(); self.config.coresize];
+        self.teams_process_queues = placement.teams_process_queues;
+        self.start_positions = placement.start_positions;
+        self.turn = 0;
+        self.cycle = 0;
+        self.death_cycles = vec![None; warriors.len()];
+        self.last_steps.clear();
+        self.history.clear();
+        return Ok(());
+    }
+
+    pub fn load(config: MarsConfig, warrior_paths: &[String]) -> Result<(Mars, Vec<Diagnostic>), LoadError> { // the match and any warnings from loading it
+        return Mars::load_edited(config, warrior_paths, &[]);
+    }
+
+    // like load, with the Redcode in sources (by team) instead of the file where it's given, for warriors edited without saving
+    pub fn load_edited(config: MarsConfig, warrior_paths: &[String], sources: &[Option<String>]) -> Result<(Mars, Vec<Diagnostic>), LoadError> {
+        // checked before the warriors are, which can't be checked against a config that doesn't make sense
+        config.check()?;
+        let (warriors, mut diagnostics) = parse_warriors(warrior_paths, sources)?;
+        // checked here first, as Mars::new doesn't know which files the warriors came from
+        diagnostics.extend(check_warriors(&warriors, &config));
+        if diagnostics.iter().any(|diagnostic| diagnostic.severity == Severity::Error) { return Err(LoadError { diagnostics }); }
+        let warriors: Vec<Warrior> = warriors.into_iter().map(|parsed| parsed.warrior).collect();
+        return match Mars::new(config, &warriors) {
+            Ok(mars) => Ok((mars, diagnostics)),
+            Err(error) => Err(LoadError { diagnostics: [diagnostics, error.diagnostics].concat() }),
+        };
+    }
+
+    pub fn empty(config: MarsConfig) -> Mars { // a core without any warriors, for when loading them failed
+        let rng = StdRng::seed_from_u64(config.seed);
+        let core = vec![config.default_instruction; config.coresize];
+        let owners = vec![None; config.coresize];
+        let activity = vec![Activity::default(); config.coresize];
+        return Mars { core, owners, owned_cells: Vec::new(), activity, teams_process_queues: Vec::new(), turn: 0, config, cycle: 0, warrior_names: Vec::new(), pspaces: Vec::new(), death_cycles: Vec::new(), start_positions: Vec::new(), last_steps: Vec::new(), history_length: 0, history: VecDeque::new(), rng };
+    }
+
+    pub fn is_alive(&self, team: usize) -> bool {
+        return !self.teams_process_queues[team].is_empty();
+    }
+
+    pub fn living_teams(&self) -> usize {
+        return (0..self.teams_process_queues.len()).filter(|team| self.is_alive(*team)).count();
+    }
+
+    fn part_step(&mut self) { // steps the team whose turn it is
+        let mut record = self.step_process();
+        record.saved_owners = record.saved_cells.map(|(address, _)| self.owners[address]);
+        for address in record.written_addresses() {
+            self.set_owner(address, Some(Owner { team: record.process.team, cycle: self.cycle, writer: Some(record.process.pointer) }));
+            self.activity[address].writes += 1;
+        }
+        for address in record.read_cells.into_iter().flatten() {
+            self.activity[address].reads += 1;
+        }
+        self.activity[record.process.pointer].executions += 1;
+        if self.teams_process_queues[self.turn].is_empty() {
+            self.death_cycles[self.turn] = Some(self.cycle);
+        }
+
+        self.last_steps.push(record);
+        if self.history_length > 0 {
+            self.history.push_back(record);
+            self.trim_history();
+        }
+    }
+
+    fn set_owner(&mut self, address: usize, owner: Option<Owner>) { // changes the owner of a cell, moving it between the teams' counts
+        if let Some(old_owner) = self.owners[address] { self.owned_cells[old_owner.team as usize] -= 1; }
+        if let Some(owner) = owner { self.owned_cells[owner.team as usize] += 1; }
+        self.owners[address] = owner;
+    }
+
+    fn trim_history(&mut self) { // forgets the steps older than history_length cycles
+        while self.history.front().is_some_and(|record| record.cycle + self.history_length <= self.cycle) {
+            self.history.pop_front();
+        }
+    }
+
+    pub fn take_history(&mut self) -> VecDeque<StepRecord> { // removes the history, oldest first, so the match can be copied without it
+        return std::mem::take(&mut self.history);
+    }
+
+    pub fn extend_history(&mut self, steps: impl IntoIterator<Item = StepRecord>) { // adds steps a copy of this match took to the history, oldest first
+        self.history.extend(steps);
+        self.trim_history();
+    }
+
+    fn undo_part_step(&mut self, record: StepRecord) { // puts the core, queue and P-space back the way they were before record's step
+        // cells are restored newest first, so a cell saved twice ends up with its oldest value
+        for ((address, instruction), owner) in record.saved_cells.into_iter().zip(record.saved_owners).rev() {
+            self.core[address] = instruction;
+            self.set_owner(address, owner);
+        }
+        for address in record.written_addresses() {
+            self.activity[address].writes -= 1;
+        }
+        for address in record.read_cells.into_iter().flatten() {
+            self.activity[address].reads -= 1;
+        }
+        self.activity[record.process.pointer].executions -= 1;
+        let team = record.process.team as usize;
+        if let Some((index, value)) = record.saved_pspace_cell {
+            self.pspaces[team][index] = value;
+        }
+
+        // the process was either killed, moved to the back, or moved to the back with a new process behind it
+        let process_queue = &mut self.teams_process_queues[team];
+        if process_queue.len() < record.queue_length {
+            self.death_cycles[team] = None;
+        } else if process_queue.len() == record.queue_length {
+            process_queue.pop_back();
+        } else {
+            process_queue.pop_back();
+            process_queue.pop_back();
+        }
+        process_queue.push_front(record.process);
+    }
+
+    pub fn statistics(&self) -> CycleStatistics { // the statistics of the match as it is now
+        let processes = self.teams_process_queues.iter().map(|process_queue| process_queue.len()).collect();
+        let owned_cells = self.owned_cells.clone();
+        let mut opcodes = [0; Opcode::ALL.len()];
+        for record in &self.last_steps {
+            opcodes[Opcode::ALL.iter().position(|opcode| *opcode == record.instruction.opcode).unwrap_or_default()] += 1;
+        }
+        return CycleStatistics { cycle: self.cycle, processes, owned_cells, opcodes };
+    }
+
+    pub fn can_step_back(&self) -> bool {
+        return self.history.back().is_some_and(|record| record.cycle + 1 == self.cycle);
+    }
+
+    pub fn oldest_cycle(&self) -> usize { // the earliest cycle that can be stepped back to
+        return self.history.front().map_or(self.cycle, |record| record.cycle);
+    }
+
+    pub fn step_back(&mut self) -> bool { // undoes the last cycle, if it's still in the history
+        if !self.can_step_back() { return false; }
+        self.cycle -= 1;
+        while let Some(record) = self.history.back().copied().filter(|record| record.cycle == self.cycle) {
+            self.history.pop_back();
+            self.undo_part_step(record);
+        }
+        self.turn = 0;
+        self.last_steps = self.history.iter().filter(|record| record.cycle + 1 == self.cycle).copied().collect();
+        return true;
+    }
+
+    pub fn step(&mut self) -> MatchResult { // steps each living team once, unless the match is already over
+        if self.result() != MatchResult::Ongoing { return self.result(); }
+        self.last_steps.clear();
+        while self.turn < self.teams_process_queues.len() && self.living_teams() > 1 {
+            if self.is_alive(self.turn) { self.part_step(); }
+            self.turn += 1;
+        }
+        self.turn = 0;
+        self.cycle += 1;
+        return self.result();
+    }
+
+    pub fn result(&self) -> MatchResult {
+        if self.living_teams() <= 1 {
+            return match (0..self.teams_process_queues.len()).find(|team| self.is_alive(*team)) {
+                Some(team) => MatchResult::Win(team as u8),
+                None => MatchResult::Tie,
+            };
+        } else if self.cycle >= self.config.max_cycles {
+            return MatchResult::Tie;
+        }
+        return MatchResult::Ongoing;
+    }
+
+    pub fn run_to_end(&mut self) -> MatchResult { // steps until a team wins or the cycle limit is reached
+        while self.result() == MatchResult::Ongoing {
+            self.step();
+        }
+        return self.result();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::{parse_test_warriors, parse_warrior};
+
+    type Snapshot = (Vec<Instruction>, Vec<Vec<(u8, usize)>>, Vec<Vec<usize>>, Vec<Option<Owner>>, Vec<Activity>, Vec<Option<usize>>, usize);
+
+    fn snapshot(mars: &Mars) -> Snapshot { // everything stepping back has to restore
+        let queues = mars.teams_process_queues.iter()
+            .map(|process_queue| process_queue.iter().map(|process| (process.team, process.pointer)).collect())
+            .collect();
+        return (mars.core.clone(), queues, mars.pspaces.clone(), mars.owners.clone(), mars.activity.clone(), mars.death_cycles.clone(), mars.cycle);
+    }
+
+    fn assert_rewinds(mut mars: Mars, cycles: usize) { // steps forward until the match ends, then back, checking every cycle matches its snapshot
+        mars.history_length = cycles;
+        let mut snapshots = vec![snapshot(&mars)];
+        for _ in 0..cycles {
+            let result = mars.step();
+            snapshots.push(snapshot(&mars));
+            if result != MatchResult::Ongoing { break; }
+        }
+        while let Some(expected) = snapshots.pop() {
+            assert!(snapshot(&mars) == expected, "cycle {} wasn't restored", expected.6);
+            assert_eq!(mars.owned_cells, count_owned_cells(&mars.owners, mars.teams_process_queues.len()));
+            if !snapshots.is_empty() { assert!(mars.step_back()); }
+        }
+        assert!(!mars.step_back());
+    }
+
+    fn warriors(paths: &[&str], seed: u64) -> Mars {
+        let mut config = MarsConfig::new(8000);
+        config.seed = seed;
+        let paths: Vec<String> = paths.iter().map(|path| path.to_string()).collect();
+        return Mars::load(config, &paths).unwrap_or_else(|error| panic!("{error}")).0;
+    }
+
+    fn small_match(paths: &[&str]) -> Mars { // the warriors spread evenly over a core of 800, so checking every cycle of a rewind stays quick
+        let mut config = MarsConfig::new(800);
+        config.positions = (0..paths.len()).map(|team| Some(team * 800 / paths.len())).collect();
+        let paths: Vec<String> = paths.iter().map(|path| path.to_string()).collect();
+        return Mars::load(config, &paths).unwrap_or_else(|error| panic!("{error}")).0;
+    }
+
+    #[test]
+    fn check_rejects_zero_limits() {
+        assert!(MarsConfig::new(8000).check().is_ok());
+        let configs: [fn(&mut MarsConfig); 6] = [
+            |config| config.coresize = 0,
+            |config| config.max_processes = 0,
+            |config| config.max_cycles = 0,
+            |config| config.pspace_size = 0,
+            |config| config.read_limit = 0,
+            |config| config.write_limit = 8001,
+        ];
+        for change in configs {
+            let mut config = MarsConfig::new(8000);
+            change(&mut config);
+            assert!(config.check().is_err());
+        }
+        assert!(Mars::load(MarsConfig::new(0), &["warriors/imp.red".to_string(), "warriors/imp.red".to_string()]).is_err());
+    }
+
+    #[test]
+    fn edited_sources_replace_their_files() {
+        let paths = ["warriors/imp.red".to_string(), "warriors/imp.red".to_string()];
+        let (mars, _) = Mars::load_edited(MarsConfig::new(8000), &paths, &[Some("jmp 0".to_string())]).unwrap_or_else(|error| panic!("{error}"));
+        assert_eq!(mars.core[mars.start_positions[0]].opcode, Opcode::Jmp);
+        assert_eq!(mars.core[mars.start_positions[1]].opcode, Opcode::Mov);
+    }
+
+    #[test]
+    fn next_round_stores_the_last_result_in_pspace() {
+        // P-space cell 0 is -1 before the first round, then 0 for a loss or the number of survivors for a win or tie
+        let sources = ["jmp 0", "dat 0, 0", "jmp 0"];
+        let warriors = parse_test_warriors(&sources);
+        let mut config = MarsConfig::new(8000);
+        config.max_cycles = 10;
+        config.positions = vec![Some(0), Some(2000), Some(4000)];
+        let mut mars = Mars::new(config.clone(), &warriors).unwrap_or_else(|error| panic!("{error}"));
+        assert!(mars.pspaces.iter().all(|pspace| pspace[0] == 8000 - 1));
+        assert_eq!(mars.run_to_end(), MatchResult::Tie);
+        mars.next_round(&warriors).unwrap_or_else(|error| panic!("{error}"));
+        assert_eq!(mars.pspaces.iter().map(|pspace| pspace[0]).collect::<Vec<usize>>(), [2, 0, 2]);
+
+        config.positions.pop();
+        let mut mars = Mars::new(config, &warriors[..2]).unwrap_or_else(|error| panic!("{error}"));
+        assert_eq!(mars.run_to_end(), MatchResult::Win(0));
+        mars.next_round(&warriors[..2]).unwrap_or_else(|error| panic!("{error}"));
+        assert_eq!(mars.pspaces.iter().map(|pspace| pspace[0]).collect::<Vec<usize>>(), [1, 0]);
+    }
+
+    #[test]
+    fn the_seed_decides_the_placement_of_every_round() {
+        let paths = ["warriors/imp.red", "warriors/DwarfScout.red", "warriors/scanner1.red"];
+        let parsed: Vec<Warrior> = paths.iter()
+            .map(|path| parse_warrior(path).unwrap_or_else(|error| panic!("{error}")).0.warrior)
+            .collect();
+        let (mut first, mut second) = (warriors(&paths, 7), warriors(&paths, 7));
+        assert_ne!(first.start_positions, warriors(&paths, 8).start_positions);
+        for _ in 0..3 {
+            assert_eq!(first.start_positions, second.start_positions);
+            first.next_round(&parsed).unwrap_or_else(|error| panic!("{error}"));
+            second.next_round(&parsed).unwrap_or_else(|error| panic!("{error}"));
+        }
+        assert_eq!(first.start_positions, second.start_positions);
+    }
+
+    #[test]
+    fn step_back_restores_duels() {
+        // the scanner kills the dwarf, and the factory splits into a dozen processes
+        assert_rewinds(small_match(&["warriors/DwarfScout.red", "warriors/scanner1.red"]), 400);
+        assert_rewinds(small_match(&["warriors/littlefactory.red", "warriors/imp.red"]), 400);
+    }
+
+    #[test]
+    fn step_back_restores_melees() {
+        assert_rewinds(small_match(&["warriors/booom.red", "warriors/impgate.red", "warriors/scanner1.red", "warriors/littlefactory.red"]), 400);
+    }
+
+    #[test]
+    fn step_back_restores_deaths_and_pspace() {
+        // the first warrior writes to P-space every cycle, then dies to its own DAT
+        let sources = ["stp.ab #1, count\nadd #1, count\ndjn -2, #50\ncount dat #0, #0", "jmp 0"];
+        let warriors = parse_test_warriors(&sources);
+        let mut config = MarsConfig::new(8000);
+        config.positions = vec![Some(0), Some(4000)];
+        let mars = Mars::new(config, &warriors).unwrap_or_else(|error| panic!("{error}"));
+        let mut played = mars.clone();
+        played.run_to_end();
+        assert!(played.death_cycles[0].is_some());
+        assert!(played.pspaces[0].iter().skip(1).any(|value| *value != 0));
+        assert_rewinds(mars, 200);
+    }
+
+    #[test]
+    fn step_back_restores_steps_taken_by_a_copy() {
+        // like the play thread, which steps a copy of the match and sends back its steps for the history
+        let mut mars = warriors(&["warriors/DwarfScout.red", "warriors/scanner1.red"], 6);
+        mars.history_length = 100;
+        let start = snapshot(&mars);
+        let mut copy = mars.clone();
+        for _ in 0..50 { copy.step(); }
+        let steps = copy.take_history();
+        let history = mars.take_history();
+        mars = copy;
+        mars.extend_history(history.into_iter().chain(steps));
+        while mars.step_back() {}
+        assert!(snapshot(&mars) == start);
+    }
+}
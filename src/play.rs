@@ -0,0 +1,152 @@
+use std::thread::{spawn, sleep};
+use std::fs::metadata;
+use std::time::{Duration, Instant, SystemTime};
+use std::collections::VecDeque;
+use std::sync::mpsc::{Sender, channel};
+use emars::{CycleStatistics, Mars, MarsConfig, MatchResult, StepRecord};
+
+use crate::EmarsApp;
+use crate::editor::WarriorEditor;
+use crate::breakpoint::{Breakpoint, Watchpoint, hit_breakpoint, hit_watchpoint};
+
+pub const HISTORY_LENGTH: usize = 1000; // the number of cycles that can be stepped back through
+pub const WATCH_INTERVAL: Duration = Duration::from_millis(250); // how often the warriors' files are checked for changes
+
+pub fn modified_times(paths: &[String]) -> Vec<Option<SystemTime>> { // when each file was last modified, if that can be read
+    return paths.iter().map(|path| metadata(path).and_then(|metadata| metadata.modified()).ok()).collect();
+}
+
+// what the play thread sends the window after each batch of steps
+pub(crate) struct PlayUpdate {
+    pub mars: Mars, // the match after the steps, without its history
+    pub steps: VecDeque<StepRecord>, // the steps taken since the last update, for the window's history
+    pub statistics: Vec<CycleStatistics>, // the statistics after each of the steps
+    pub stop_reason: Option<String>, // why play stopped, if a breakpoint or watchpoint was hit
+    pub ended: bool, // whether the match ended, which stops play too
+}
+
+// the state the play thread steps the match with
+struct PlayThread {
+    mars: Mars, // the thread's copy of the match
+    breakpoints: Vec<Breakpoint>, // stop play before any of these are executed
+    watchpoints: Vec<Watchpoint>, // stop play after any of these addresses are written to
+    play_delay: usize, // the number of milliseconds per step
+    last_step: Instant, // the time since the last step
+    sender: Sender<PlayUpdate>, // sends the window an update after each batch of steps
+}
+
+fn start_play_thread(app: &mut EmarsApp) {
+    // the history stays with the window, which gets the new steps with every update
+    let history = app.mars.take_history();
+    let mut thread = PlayThread {
+        mars: app.mars.clone(),
+        breakpoints: app.breakpoints.clone(),
+        watchpoints: app.watchpoints.clone(),
+        play_delay: app.play_delay,
+        last_step: app.last_step,
+        sender: app.state_sender.clone(),
+    };
+    app.mars.extend_history(history);
+    spawn(move || {
+        loop {
+            if !thread.process_playing() { break; }
+            sleep(Duration::from_millis(10))
+        }
+    });
+}
+
+impl PlayThread {
+    fn process_playing(&mut self) -> bool {
+        if self.last_step.elapsed().as_millis() as usize > self.play_delay {
+            let mut ended: bool = false;
+            let mut stop_reason: Option<String> = None;
+            let mut statistics: Vec<CycleStatistics> = Vec::new();
+            for _ in 0..(self.last_step.elapsed().as_millis() as usize / self.play_delay) {
+                let result = self.mars.step();
+                statistics.push(self.mars.statistics());
+                if result != MatchResult::Ongoing { ended = true; break; }
+                // breakpoints are checked after stepping, so playing again after one is hit moves past it
+                stop_reason = hit_watchpoint(&self.watchpoints, &self.mars).or_else(|| hit_breakpoint(&self.breakpoints, &self.mars));
+                if stop_reason.is_some() { break; }
+            }
+            self.last_step = Instant::now();
+            let steps = self.mars.take_history();
+            match self.sender.send(PlayUpdate { mars: self.mars.clone(), steps, statistics, stop_reason: stop_reason.clone(), ended }) {
+                Ok(_) => {},
+                Err(_) => return false,
+            }
+            if ended || stop_reason.is_some() { return false; }
+        }
+        return true;
+    }
+}
+
+impl EmarsApp {
+    pub fn receive_update(&mut self, update: PlayUpdate) { // moves the match on to the play thread's, keeping the history
+        let history = self.mars.take_history();
+        self.mars = update.mars;
+        self.mars.extend_history(history.into_iter().chain(update.steps));
+        self.statistics.extend(update.statistics);
+    }
+
+    fn load(&mut self, config: MarsConfig) -> bool { // reloads the warriors and starts a new match with config, returning whether they loaded
+        if self.playing { self.press_play(); }
+        self.modified_times = modified_times(&self.warrior_paths);
+        // warriors assembled in an editor but not saved yet are loaded from there instead of their file
+        let sources: Vec<Option<String>> = self.editors.iter().map(|editor| editor.assembled.clone()).collect();
+        match Mars::load_edited(config, &self.warrior_paths, &sources) {
+            Ok((mars, warnings)) => { self.mars = mars; self.diagnostics = warnings; }
+            // the match that was running is kept, along with its timeline and statistics
+            Err(error) => { self.diagnostics = error.diagnostics; return false; }
+        }
+        self.mars.history_length = HISTORY_LENGTH;
+        self.timeline_end = 0;
+        self.stop_reason = None;
+        self.statistics.clear();
+        return true;
+    }
+
+    pub fn seek(&mut self, cycle: usize) { // steps back or forward until the match is at cycle, or as close as it can get
+        if self.playing { self.press_play(); }
+        while self.mars.cycle > cycle && self.mars.step_back() {}
+        while self.mars.cycle < cycle {
+            let result = self.mars.step();
+            // every cycle stepped through is charted, not just the one the timeline stops at
+            self.statistics.update(&self.mars);
+            if result != MatchResult::Ongoing { break; }
+        }
+    }
+
+    pub fn restart(&mut self) { // reloads the warriors and starts a new match with the current config
+        self.load(self.config.clone());
+    }
+
+    pub fn reload(&mut self) -> bool { // reloads the warriors into the same match, keeping its seed and every warrior's start position, returning whether they loaded
+        let mut config = self.mars.config.clone();
+        config.positions = (0..self.warrior_paths.len()).map(|team| self.mars.start_positions.get(team).copied()).collect();
+        return self.load(config);
+    }
+
+    pub fn watch_files(&mut self) { // reloads the match once any warrior's file has been changed on disk
+        if !self.watching || self.last_watch.elapsed() < WATCH_INTERVAL { return; }
+        self.last_watch = Instant::now();
+        if modified_times(&self.warrior_paths) == self.modified_times { return; }
+        // closed editors are re-read from the file the next time they're opened, unless they have unsaved source in the match
+        for editor in self.editors.iter_mut().filter(|editor| !editor.open && editor.assembled.is_none()) {
+            *editor = WarriorEditor::default();
+        }
+        self.reload();
+    }
+
+    pub fn press_play(&mut self) {
+        if !self.playing {
+            self.playing = true;
+            self.stop_reason = None;
+            self.last_step = Instant::now();
+            start_play_thread(self);
+        } else {
+            self.playing = false;
+            (self.state_sender, self.state_receiver) = channel::<PlayUpdate>();
+        }
+    }
+}
@@ -0,0 +1,59 @@
+use crate::mars::MarsConfig;
+use crate::sim::Opcode;
+
+// the standard settings of the usual hills
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Preset {
+    Nop94, // ICWS'94 without P-space
+    Draft94, // ICWS'94 draft, with P-space
+    Tiny,
+    Nano,
+    LimitedProcess,
+    Icws88,
+}
+
+impl Preset {
+    pub const ALL: [Preset; 6] = [Preset::Nop94, Preset::Draft94, Preset::Tiny, Preset::Nano, Preset::LimitedProcess, Preset::Icws88];
+
+    pub fn name(&self) -> &'static str {
+        return match self {
+            Preset::Nop94 => "94nop",
+            Preset::Draft94 => "94draft",
+            Preset::Tiny => "tiny",
+            Preset::Nano => "nano",
+            Preset::LimitedProcess => "lp",
+            Preset::Icws88 => "88",
+        };
+    }
+
+    pub fn from_name(name: &str) -> Option<Preset> {
+        return Preset::ALL.into_iter().find(|preset| preset.name().eq_ignore_ascii_case(name));
+    }
+
+    pub fn config(&self) -> MarsConfig {
+        // coresize, max processes, max cycles, max length, min distance
+        let (coresize, max_processes, max_cycles, max_length, min_distance) = match self {
+            Preset::Nop94 | Preset::Draft94 | Preset::Icws88 => (8000, 8000, 80000, 100, 100),
+            Preset::Tiny => (800, 800, 8000, 20, 20),
+            Preset::Nano => (80, 80, 800, 5, 5),
+            Preset::LimitedProcess => (8000, 8, 80000, 200, 200),
+        };
+        let allowed_opcodes: Vec<Opcode> = match self {
+            Preset::Draft94 => Opcode::ALL.to_vec(),
+            Preset::Icws88 => vec![
+                Opcode::Dat, Opcode::Mov, Opcode::Add, Opcode::Sub, Opcode::Jmp, Opcode::Jmz,
+                Opcode::Jmn, Opcode::Djn, Opcode::Cmp, Opcode::Slt, Opcode::Spl,
+            ],
+            _ => Opcode::ALL.into_iter().filter(|opcode| *opcode != Opcode::Ldp && *opcode != Opcode::Stp).collect(),
+        };
+
+        let mut config = MarsConfig::new(coresize);
+        config.max_processes = max_processes;
+        config.max_cycles = max_cycles;
+        config.max_length = max_length;
+        config.min_distance = min_distance;
+        config.allowed_opcodes = allowed_opcodes;
+        config.icws88 = *self == Preset::Icws88;
+        return config;
+    }
+}
@@ -1,50 +1,114 @@
-#![allow(unused_mut, unused_imports)]
+#![allow(unused_mut)]
 
-use corewars_core::load_file::{AddressMode, Modifier, Field as OtherField, Instruction as OtherInstruction, Opcode, Value::Literal};
+use corewars_core::load_file::{AddressMode, Modifier, Instruction as OtherInstruction, Opcode as OtherOpcode, Value::Literal, Warrior};
 use corewars_parser as parser;
-use egui::Label;
+use std::borrow::Cow;
+use std::cmp::max;
+use std::fmt;
 use std::fs::read_to_string;
-use std::collections::{vec_deque, VecDeque};
-use std::thread::{spawn, sleep};
-use std::time::{Duration, Instant};
-use std::thread;
-use std::sync::mpsc::{Sender, Receiver, channel};
+use std::collections::VecDeque;
 use rand::Rng;
 
-use crate::EmarsApp;
+use crate::mars::{Mars, MarsConfig, Owner, StepRecord};
+
+// corewars_core's opcodes plus the '94 P-space extensions, which it doesn't support
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Opcode {
+    Dat, Mov, Add, Sub, Mul, Div, Mod,
+    Jmp, Jmz, Jmn, Djn, Cmp, Seq, Sne,
+    Slt, Spl, Nop, Ldp, Stp,
+}
+
+impl Opcode {
+    pub const ALL: [Opcode; 19] = [
+        Opcode::Dat, Opcode::Mov, Opcode::Add, Opcode::Sub, Opcode::Mul, Opcode::Div, Opcode::Mod,
+        Opcode::Jmp, Opcode::Jmz, Opcode::Jmn, Opcode::Djn, Opcode::Cmp, Opcode::Seq, Opcode::Sne,
+        Opcode::Slt, Opcode::Spl, Opcode::Nop, Opcode::Ldp, Opcode::Stp,
+    ];
+}
+
+impl From<OtherOpcode> for Opcode {
+    fn from(opcode: OtherOpcode) -> Opcode {
+        return match opcode {
+            OtherOpcode::Dat => Opcode::Dat,
+            OtherOpcode::Mov => Opcode::Mov,
+            OtherOpcode::Add => Opcode::Add,
+            OtherOpcode::Sub => Opcode::Sub,
+            OtherOpcode::Mul => Opcode::Mul,
+            OtherOpcode::Div => Opcode::Div,
+            OtherOpcode::Mod => Opcode::Mod,
+            OtherOpcode::Jmp => Opcode::Jmp,
+            OtherOpcode::Jmz => Opcode::Jmz,
+            OtherOpcode::Jmn => Opcode::Jmn,
+            OtherOpcode::Djn => Opcode::Djn,
+            OtherOpcode::Cmp => Opcode::Cmp,
+            OtherOpcode::Seq => Opcode::Seq,
+            OtherOpcode::Sne => Opcode::Sne,
+            OtherOpcode::Slt => Opcode::Slt,
+            OtherOpcode::Spl => Opcode::Spl,
+            OtherOpcode::Nop => Opcode::Nop,
+        };
+    }
+}
+
+impl fmt::Display for Opcode {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.pad(&format!("{self:?}").to_uppercase())
+    }
+}
 
 #[derive(Clone, Copy)]
 pub struct Process {
-    pub(crate) team: u8,
-    pub(crate) pointer: usize
+    pub team: u8,
+    pub pointer: usize
 }
 
 #[derive(Clone, Copy, PartialEq)]
 pub struct Field {
-    pub(crate) address_mode: AddressMode,
-    pub(crate) value: usize
+    pub address_mode: AddressMode,
+    pub value: usize
 }
 
 #[derive(Clone, Copy, PartialEq)]
 pub struct Instruction {
-    pub(crate) opcode: Opcode,
-    pub(crate) modifier: Modifier,
-    pub(crate) field_a: Field,
-    pub(crate) field_b: Field
+    pub opcode: Opcode,
+    pub modifier: Modifier,
+    pub field_a: Field,
+    pub field_b: Field
 }
 
+pub const DEFAULT_INSTRUCTION: Instruction = Instruction { // DAT.F #0, #0
+    opcode: Opcode::Dat,
+    modifier: Modifier::F,
+    field_a: Field { address_mode: AddressMode::Immediate, value: 0 },
+    field_b: Field { address_mode: AddressMode::Immediate, value: 0 },
+};
+
+// LDP and STP are assembled as NOPs with this added to (LDP) or subtracted from (STP) their A field, see rewrite_pspace_opcodes
+// NOPs, LDPs and STPs whose A fields would be read as another of the three are rejected, see find_misread_instruction
+const PSPACE_MARKER: i32 = 1_000_000_000;
+
 fn translate_instruction(old_instruction: OtherInstruction, coresize: usize) -> Instruction {
-    let field_a_value = match old_instruction.field_a.value {
-        Literal(n) => negative_mod(n as isize, coresize),
+    let mut opcode = Opcode::from(old_instruction.opcode);
+    let mut field_a_literal = match old_instruction.field_a.value {
+        Literal(n) => n,
         _ => panic!("corewars_core Value::Label found while translating field A to crate::sim::Field")
     };
+    if opcode == Opcode::Nop && field_a_literal >= PSPACE_MARKER / 2 {
+        opcode = Opcode::Ldp;
+        field_a_literal -= PSPACE_MARKER;
+    } else if opcode == Opcode::Nop && field_a_literal <= -PSPACE_MARKER / 2 {
+        opcode = Opcode::Stp;
+        field_a_literal += PSPACE_MARKER;
+    }
+    let field_a_value = negative_mod(field_a_literal as isize, coresize);
     let field_b_value = match old_instruction.field_b.value {
         Literal(n) => negative_mod(n as isize, coresize),
         _ => panic!("corewars_core Value::Label found while translating field B to crate::sim::Field")
     };
 
     return Instruction {
-        opcode: old_instruction.opcode,
+        opcode,
         modifier: old_instruction.modifier,
         field_a: Field {
             address_mode: old_instruction.field_a.address_mode,
@@ -57,6 +121,7 @@ fn translate_instruction(old_instruction: OtherInstruction, coresize: usize) ->
     }
 }
 
+#[allow(clippy::clone_on_copy)]
 fn negative_mod(n: isize, modulus: usize) -> usize {
     let mut value = n.clone();
     while value.is_negative() {
@@ -83,574 +148,1398 @@ fn increment_mod(n: &mut usize, modulus: usize) {
     else { *n += 1; }
 }
 
-pub fn init(warrior_a_path: String, warrior_b_path: String, coresize: usize, default_instruction: Instruction) -> (Vec<Instruction>, Vec<VecDeque<Process>>) {
-    // if coresize <= 400 { panic!("Core too small") }
+// splits an LDP/STP opcode token like "ldp.ab" into the sign of the marker for its A field and its modifier, if it has one
+fn parse_pspace_opcode(token: &str) -> Option<(i32, Option<String>)> {
+    let token = token.to_uppercase();
+    let (opcode, modifier) = match token.split_once('.') {
+        Some((opcode, modifier)) => (opcode, Some(modifier.to_string())),
+        None => (token.as_str(), None),
+    };
+    let sign = match opcode {
+        "LDP" => 1,
+        "STP" => -1,
+        _ => return None,
+    };
+    if modifier.as_ref().is_some_and(|modifier| !MODIFIERS.contains(&modifier.as_str())) {
+        return None;
+    }
+    return Some((sign, modifier));
+}
 
-    let warrior_a_file_string= read_to_string(warrior_a_path.as_str()).expect("Could not find/access Warrior A's file");
-    let warrior_a = match corewars_parser::parse(warrior_a_file_string.as_str()) { // this one's yoinked straight from the source
-        parser::Result::Ok(warrior, warnings) => { 
-            println!("Warrior A warnings: {:?}", &warnings);
-            Ok(warrior)
+fn first_tokens(code: &str) -> Vec<(usize, usize)> { // where the first two tokens of a line of code start and end
+    // the opcode is either the first token or the one right after a label, which can end in a colon
+    let is_separator = |c: char| c.is_whitespace() || c == ':';
+    let mut tokens: Vec<(usize, usize)> = Vec::new();
+    let mut token_end: usize = 0;
+    while tokens.len() < 2 {
+        let Some(n) = code[token_end..].find(|c: char| !is_separator(c)) else { break; };
+        let token_start = token_end + n;
+        token_end = match code[token_start..].find(is_separator) { Some(n) => token_start + n, None => code.len() };
+        tokens.push((token_start, token_end));
+    }
+    return tokens;
+}
+
+fn first_words(code: &str) -> Vec<&str> { // the first two tokens of a line of code
+    return first_tokens(code).into_iter().map(|(start, end)| &code[start..end]).collect();
+}
+
+fn find_opcode(code: &str) -> Option<(usize, usize)> { // where the opcode token of a line starts and ends, if it has one
+    return first_tokens(code).into_iter().find(|(start, end)| {
+        let opcode = code[*start..*end].split('.').next().unwrap_or_default();
+        Opcode::ALL.iter().any(|other| other.to_string().eq_ignore_ascii_case(opcode))
+    });
+}
+
+fn rewrite_pspace_line(line: &str, marker: i32) -> String {
+    let (code, comment) = line.split_at(line.find(';').unwrap_or(line.len()));
+    let Some((token_start, token_end)) = find_opcode(code) else { return line.to_string(); };
+    let Some((sign, modifier)) = parse_pspace_opcode(&code[token_start..token_end]) else { return line.to_string(); };
+
+    let Some((field_a, field_b)) = code[token_end..].split_once(',') else { return line.to_string(); };
+    let (field_a, field_b) = (field_a.trim(), field_b.trim());
+    let address_mode = |field: &str| field.chars().next().filter(|c| "#$*@{<}>".contains(*c));
+    let field_a_mode = address_mode(field_a);
+    let field_a_expression = &field_a[field_a_mode.map_or(0, |c| c.len_utf8())..];
+
+    // same defaults as the other non-MOV two-operand instructions
+    let modifier = modifier.unwrap_or_else(|| {
+        if field_a_mode == Some('#') { "AB".to_string() } else { "B".to_string() }
+    });
+    return format!(
+        "{label}NOP.{modifier} {mode}({field_a_expression}){marker:+}, {field_b}{comment}",
+        label = &code[..token_start],
+        mode = field_a_mode.map_or(String::new(), |c| c.to_string()),
+        marker = sign * marker,
+    );
+}
+
+// corewars_parser doesn't know LDP and STP, so they're rewritten into NOPs whose A field is pushed
+// far out of any real address range by marker, and turned back by translate_instruction
+fn rewrite_pspace_opcodes(source: &str, marker: i32) -> String {
+    return source.lines().map(|line| rewrite_pspace_line(line, marker)).collect::<Vec<String>>().join("\n");
+}
+
+// an LDP or STP without a comma isn't rewritten, which would leave the parser to drop it as a label
+fn find_missing_pspace_operand(source: &str) -> Option<(usize, Opcode)> { // the first LDP or STP line with only one operand, counting from 1
+    for (i, line) in source.lines().enumerate() {
+        let code = line.split(';').next().unwrap_or_default();
+        if first_words(code).iter().any(|token| token.eq_ignore_ascii_case("END")) { break; } // the parser stops reading at END
+        let Some((token_start, token_end)) = find_opcode(code) else { continue; };
+        let Some((sign, _)) = parse_pspace_opcode(&code[token_start..token_end]) else { continue; };
+        if !code[token_end..].contains(',') { return Some((i + 1, if sign > 0 { Opcode::Ldp } else { Opcode::Stp })); }
+    }
+    return None;
+}
+
+fn is_pspace_marked(instruction: &OtherInstruction) -> bool { // whether translate_instruction will read an instruction as LDP or STP
+    return instruction.opcode == OtherOpcode::Nop && matches!(instruction.field_a.value, Literal(n) if n.unsigned_abs() >= PSPACE_MARKER as u32 / 2);
+}
+
+// a NOP written with an A field as big as the marker can't be told apart from LDP or STP, nor can an LDP or STP whose
+// A field cancels the marker out, so the source is assembled again without the marker, which leaves the A fields
+// of everything but LDP and STP the same
+fn find_misread_instruction(source: &str, warrior: &Warrior) -> Option<(usize, String)> { // the index of an instruction translate_instruction would read as another opcode, and why
+    let instructions = &warrior.program.instructions;
+    let has_pspace_opcodes = source.lines().any(|line| rewrite_pspace_line(line, 0) != line);
+    if !has_pspace_opcodes && !instructions.iter().any(is_pspace_marked) { return None; }
+    let Ok(parser::Result::Ok(unmarked, _)) = parse_redcode(&rewrite_pspace_opcodes(source, 0)) else { return None; };
+    return instructions.iter().zip(&unmarked.program.instructions).enumerate().find_map(|(i, (marked, unmarked))| {
+        let (&Literal(marked_a), &Literal(unmarked_a)) = (&marked.field_a.value, &unmarked.field_a.value) else { return None; };
+        let read_as = if !is_pspace_marked(marked) { Opcode::Nop } else if marked_a > 0 { Opcode::Ldp } else { Opcode::Stp };
+        let message = match marked_a.wrapping_sub(unmarked_a).signum() {
+            1 if read_as != Opcode::Ldp => format!("LDP's A field must be bigger than -{}", PSPACE_MARKER / 2),
+            -1 if read_as != Opcode::Stp => format!("STP's A field must be smaller than {}", PSPACE_MARKER / 2),
+            0 if read_as != Opcode::Nop => format!("NOP's A field must be smaller than {}", PSPACE_MARKER / 2),
+            _ => return None,
+        };
+        return Some((i, message));
+    });
+}
+
+fn instruction_lines(source: &str, count: usize) -> Vec<Option<usize>> { // the line of each of count instructions, counting from 1, if they can be matched up
+    let lines: Vec<usize> = source.lines().enumerate()
+        .filter(|(_, line)| find_opcode(line.split(';').next().unwrap_or_default())
+            .is_some_and(|(start, end)| !["ORG", "END"].contains(&line[start..end].to_uppercase().as_str())))
+        .map(|(i, _)| i + 1)
+        .collect();
+    // lines that only become instructions once an EQU is expanded throw the count off, so nothing is guessed then
+    if lines.len() != count { return vec![None; count]; }
+    return lines.into_iter().map(Some).collect();
+}
+
+pub const PSEUDO_OPCODES: [&str; 6] = ["EQU", "ORG", "END", "FOR", "ROF", "PIN"];
+pub const MODIFIERS: [&str; 7] = ["A", "B", "AB", "BA", "F", "X", "I"];
+
+fn is_opcode_token(token: &str) -> bool { // whether a token is an opcode, with a modifier the parser knows if it has one
+    let (opcode, modifier) = match token.split_once('.') {
+        Some((opcode, modifier)) => (opcode, Some(modifier)),
+        None => (token, None),
+    };
+    return Opcode::ALL.iter().any(|other| other.to_string().eq_ignore_ascii_case(opcode))
+        && modifier.is_none_or(|modifier| MODIFIERS.iter().any(|other| other.eq_ignore_ascii_case(modifier)));
+}
+
+// corewars_parser reads the first word of a line without an opcode as a label and drops the rest, so a misspelled opcode loses its instruction
+fn find_dropped_lines(source: &str) -> Vec<(usize, String)> { // the lines with operands but no opcode, counting from 1, and what's wrong with each
+    let code_lines: Vec<&str> = source.lines().map(|line| line.split(';').next().unwrap_or_default()).collect();
+    // an EQU name expands into the lines it stands for, so it counts as an opcode
+    let equ_names: Vec<&str> = code_lines.iter().map(|code| first_words(code))
+        .filter(|tokens| tokens.len() == 2 && tokens[1].eq_ignore_ascii_case("EQU"))
+        .map(|tokens| tokens[0])
+        .collect();
+    let is_opcode = |token: &str| {
+        is_opcode_token(token)
+            || PSEUDO_OPCODES.iter().any(|pseudo_opcode| pseudo_opcode.eq_ignore_ascii_case(token))
+            || equ_names.contains(&token)
+    };
+    let mut dropped: Vec<(usize, String)> = Vec::new();
+    for (i, code) in code_lines.iter().enumerate() {
+        let tokens = first_words(code);
+        if tokens.iter().any(|token| token.eq_ignore_ascii_case("END")) { break; } // the parser stops reading at END
+        if tokens.len() < 2 || tokens.iter().any(|token| is_opcode(token)) { continue; }
+        // an opcode with a modifier the parser doesn't know is dropped just the same
+        let message = match tokens.iter().find_map(|token| token.split_once('.').filter(|(opcode, _)| is_opcode_token(opcode))) {
+            Some((opcode, modifier)) => format!("{} has no .{} modifier", opcode.to_uppercase(), modifier.to_uppercase()),
+            None => "this line has no opcode, is it misspelled?".to_string(),
+        };
+        dropped.push((i + 1, message));
+    }
+    return dropped;
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Severity {
+    Warning, // the warrior loaded, but probably not the way it was meant to
+    Error, // the warrior or the match couldn't be loaded
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub path: Option<String>, // the warrior file it's about, if it's about just one
+    pub line: Option<usize>, // the line in that file counting from 1, if it could be found
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let severity = match self.severity { Severity::Warning => "warning", Severity::Error => "error" };
+        match (&self.path, self.line) {
+            (Some(path), Some(line)) => write!(f, "{severity}: {path}:{line}: {}", self.message),
+            (Some(path), None) => write!(f, "{severity}: {path}: {}", self.message),
+            (None, _) => write!(f, "{severity}: {}", self.message),
         }
-        parser::Result::Err(warrior, warnings) => {
-            println!("Warrior A warnings: {:?}", &warnings);
-            Err(warrior)
+    }
+}
+
+// why loading a match failed, along with every warning found on the way
+#[derive(Clone, PartialEq, Debug)]
+pub struct LoadError {
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let lines: Vec<String> = self.diagnostics.iter().map(|diagnostic| diagnostic.to_string()).collect();
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+impl From<String> for LoadError { // errors that aren't about a single file, like warriors not fitting in the core
+    fn from(message: String) -> LoadError {
+        return LoadError { diagnostics: vec![Diagnostic { severity: Severity::Error, path: None, line: None, message }] };
+    }
+}
+
+fn find_word_line(source: &str, word: &str) -> Option<usize> { // the first line using word outside of a comment, counting from 1
+    return source.lines().position(|line| {
+        let code = line.split(';').next().unwrap_or_default();
+        code.split(|c: char| !(c.is_alphanumeric() || c == '_')).any(|other| other.eq_ignore_ascii_case(word))
+    }).map(|i| i + 1);
+}
+
+fn find_code_line(source: &str, code: &str) -> Option<usize> { // the first line containing code, counting from 1
+    let code = code.trim();
+    if code.is_empty() { return None; }
+    return source.lines().position(|line| line.contains(code)).map(|i| i + 1);
+}
+
+// corewars_parser doesn't report lines (and only sees lines after EQUs are expanded), so they're looked up in the source
+fn warning_diagnostic(path: &str, source: &str, warning: &parser::Warning) -> Diagnostic {
+    let line = match warning {
+        parser::Warning::EmptySubstitution(label) | parser::Warning::EmptyOffset(label) => find_word_line(source, label),
+        parser::Warning::OriginRedefinition { .. } => None,
+    };
+    return Diagnostic { severity: Severity::Warning, path: Some(path.to_string()), line, message: warning.to_string() };
+}
+
+fn error_diagnostic(path: &str, source: &str, error: &parser::Error) -> Diagnostic {
+    let (message, line) = match error {
+        parser::Error::InvalidSyntax(syntax_error) => (format!("invalid syntax, {}", syntax_error.variant.message()), find_code_line(source, syntax_error.line())),
+        parser::Error::InvalidArguments { opcode } => {
+            // a line using the opcode without a comma is most likely the one missing an operand
+            let opcode = opcode.to_string();
+            let line = source.lines().position(|line| !line.split(';').next().unwrap_or_default().contains(',') && find_word_line(line, &opcode).is_some());
+            (error.to_string(), line.map(|i| i + 1).or_else(|| find_word_line(source, &opcode)))
         }
-    }.unwrap();
+        parser::Error::LabelNotFound { label, line } => (error.to_string(), line.or_else(|| find_word_line(source, label))),
+        parser::Error::InvalidOrigin(_) => (error.to_string(), find_word_line(source, "org")),
+    };
+    return Diagnostic { severity: Severity::Error, path: Some(path.to_string()), line, message };
+}
+
+fn label_words(code: &str) -> impl Iterator<Item = &str> { // every word in code that could be a label
+    return code.split(|c: char| !(c.is_alphanumeric() || c == '_')).filter(|word| word.starts_with(|c: char| c.is_alphabetic() || c == '_'));
+}
 
-    let warrior_b_file_string= read_to_string(warrior_b_path.as_str()).expect("Could not find/access Warrior B's file");
-    let warrior_b = match corewars_parser::parse(warrior_b_file_string.as_str()) { // this one's yoinked straight from the source
-        parser::Result::Ok(warrior, warnings) => { 
-            println!("Warrior B warnings: {:?}", &warnings);
-            Ok(warrior)
+// corewars_parser panics on a missing label instead of returning LabelNotFound, so they're looked for before parsing
+fn find_missing_label(source: &str) -> Option<(String, usize)> { // the first label used without being declared and its line, counting from 1
+    let mut declared: Vec<&str> = Vec::new();
+    let mut used: Vec<(&str, usize)> = Vec::new();
+    for (i, line) in source.lines().enumerate() {
+        let code = line.split(';').next().unwrap_or_default();
+        let pseudo_opcode = first_tokens(code).into_iter()
+            .map(|(start, end)| (start, end, code[start..end].to_uppercase()))
+            .find(|(_, _, token)| ["EQU", "ORG", "END"].contains(&token.as_str()));
+        // labels are declared before the opcode and used after it, and EQU bodies are only checked once they're expanded, by the parser
+        let (declarations, uses) = match (find_opcode(code), &pseudo_opcode) {
+            (Some((start, end)), _) => (&code[..start], &code[end..]),
+            (None, Some((start, _, token))) if token == "EQU" => (&code[..*start], ""),
+            (None, Some((_, end, _))) => ("", &code[*end..]),
+            (None, None) => (code, ""),
+        };
+        declared.extend(label_words(declarations));
+        used.extend(label_words(uses).map(|label| (label, i + 1)));
+        if pseudo_opcode.is_some_and(|(_, _, token)| token == "END") { break; }
+    }
+    return used.into_iter().find(|(label, _)| !declared.contains(label)).map(|(label, line)| (label.to_string(), line));
+}
+
+// corewars_parser panics on some other input it can't read too, which is reported instead of taking the app down with it
+fn parse_redcode(source: &str) -> Result<parser::Result<Warrior>, String> { // the parser's result, or the message it panicked with
+    let payload = match std::panic::catch_unwind(|| corewars_parser::parse(source)) {
+        Ok(result) => return Ok(result),
+        Err(payload) => payload,
+    };
+    return Err(match payload.downcast_ref::<String>() {
+        Some(message) => message.clone(),
+        None => payload.downcast_ref::<&str>().map_or(String::new(), |message| message.to_string()),
+    });
+}
+
+// a parsed warrior along with its source, so later problems with it can be pointed out in its file
+pub struct ParsedWarrior {
+    pub warrior: Warrior,
+    pub path: String, // the file it was read from
+    pub source: String, // the Redcode it was parsed from
+}
+
+pub fn parse_warrior(path: &str) -> Result<(ParsedWarrior, Vec<Diagnostic>), LoadError> { // the warrior and any warnings from parsing it
+    let file_string = match read_to_string(path) {
+        Ok(file_string) => file_string,
+        Err(error) => return Err(LoadError { diagnostics: vec![
+            Diagnostic { severity: Severity::Error, path: Some(path.to_string()), line: None, message: format!("could not read the file: {error}") }
+        ]}),
+    };
+    return parse_warrior_source(path, &file_string);
+}
+
+pub fn parse_warrior_source(path: &str, file_string: &str) -> Result<(ParsedWarrior, Vec<Diagnostic>), LoadError> { // like parse_warrior, for source that isn't saved yet
+    // LDP and STP lines are rewritten one for one, so line numbers in the rewritten source still match the file
+    let source = rewrite_pspace_opcodes(file_string, PSPACE_MARKER);
+    if let Some((label, line)) = find_missing_label(file_string) {
+        let error = parser::Error::LabelNotFound { label, line: Some(line) };
+        return Err(LoadError { diagnostics: vec![error_diagnostic(path, &source, &error)] });
+    }
+    if let Some((line, opcode)) = find_missing_pspace_operand(file_string) {
+        let message = format!("expected additional arguments for {opcode} opcode");
+        return Err(LoadError { diagnostics: vec![Diagnostic { severity: Severity::Error, path: Some(path.to_string()), line: Some(line), message }] });
+    }
+    let result = match parse_redcode(&source) {
+        Ok(result) => result,
+        Err(message) => return Err(LoadError { diagnostics: vec![
+            Diagnostic { severity: Severity::Error, path: Some(path.to_string()), line: None, message: format!("could not be parsed: {message}") }
+        ]}),
+    };
+    match result {
+        parser::Result::Ok(mut warrior, warnings) => {
+            if warrior.metadata.name.is_none() { warrior.metadata.name = Some(path.to_string()); }
+            let mut diagnostics: Vec<Diagnostic> = warnings.iter().map(|warning| warning_diagnostic(path, &source, warning)).collect();
+            if let Some((i, message)) = find_misread_instruction(file_string, &warrior) {
+                let line = instruction_lines(file_string, warrior.program.instructions.len())[i];
+                diagnostics.push(Diagnostic { severity: Severity::Error, path: Some(path.to_string()), line, message });
+                return Err(LoadError { diagnostics });
+            }
+            let dropped = find_dropped_lines(file_string);
+            if !dropped.is_empty() {
+                diagnostics.extend(dropped.into_iter().map(|(line, message)| Diagnostic {
+                    severity: Severity::Error, path: Some(path.to_string()), line: Some(line), message
+                }));
+                return Err(LoadError { diagnostics });
+            }
+            return Ok((ParsedWarrior { warrior, path: path.to_string(), source: file_string.to_string() }, diagnostics));
         }
-        parser::Result::Err(warrior, warnings) => {
-            println!("Warrior B warnings: {:?}", &warnings);
-            Err(warrior)
+        parser::Result::Err(error, warnings) => {
+            let mut diagnostics: Vec<Diagnostic> = warnings.iter().map(|warning| warning_diagnostic(path, &source, warning)).collect();
+            diagnostics.push(error_diagnostic(path, &source, &error));
+            return Err(LoadError { diagnostics });
         }
-    }.unwrap();
+    }
+}
 
-    let mut core = vec![default_instruction; coresize];
+#[cfg(test)]
+pub(crate) fn parse_test_warriors(sources: &[&str]) -> Vec<Warrior> { // the warrior in each source, panicking if one doesn't parse
+    return sources.iter()
+        .map(|source| parse_warrior_source("test.red", source).unwrap_or_else(|error| panic!("{error}")).0.warrior)
+        .collect();
+}
 
-    let warrior_a_origin: usize = match warrior_a.program.origin {Some(n) => n as usize, None => 0};
-    let warrior_a_instructions = warrior_a.program.instructions;
-    let mut signed_index: isize;
-    let mut index: usize;
-    for i in 0..warrior_a_instructions.len() {
-        core[i] = translate_instruction(warrior_a_instructions[i].clone(), coresize);
+// parses every file, so all of their errors are reported at once, using the Redcode in sources (by team) instead of the file where it's given
+pub fn parse_warriors(paths: &[String], sources: &[Option<String>]) -> Result<(Vec<ParsedWarrior>, Vec<Diagnostic>), LoadError> {
+    let mut warriors: Vec<ParsedWarrior> = Vec::new();
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+    let mut failed = false;
+    for (team, path) in paths.iter().enumerate() {
+        let parsed = match sources.get(team).and_then(Option::as_deref) {
+            Some(source) => parse_warrior_source(path, source),
+            None => parse_warrior(path),
+        };
+        match parsed {
+            Ok((warrior, warnings)) => { warriors.push(warrior); diagnostics.extend(warnings); }
+            Err(error) => { failed = true; diagnostics.extend(error.diagnostics); }
+        }
     }
-    let process_a = Process { team: 0, pointer: warrior_a_origin };
+    if failed { return Err(LoadError { diagnostics }); }
+    return Ok((warriors, diagnostics));
+}
 
-    let mut rng = rand::rng();
-    let warrior_b_origin: usize = match warrior_b.program.origin {Some(n) => n as usize, None => 0}; 
-    let warrior_b_lower_bound = warrior_a_instructions.len() - warrior_a_origin + coresize/80;
-    let warrior_b_upper_bound = coresize - warrior_a_origin - coresize/80;
-    let warrior_b_offset = rng.random_range(warrior_b_lower_bound..warrior_b_upper_bound) as isize;
-    let warrior_b_instructions = warrior_b.program.instructions;
-    for i in 0..warrior_b_instructions.len() {
-        signed_index = i as isize - warrior_b_origin as isize + warrior_b_offset;
-        index = negative_mod(signed_index, coresize);
-        core[index] = translate_instruction(warrior_b_instructions[i].clone(), coresize);
+fn check_icws88_instruction(instruction: &OtherInstruction, opcode: Opcode) -> Result<(), String> { // checks an instruction only uses what ICWS'88 has
+    let (mode_a, mode_b) = (instruction.field_a.address_mode, instruction.field_b.address_mode);
+    if [Opcode::Seq, Opcode::Sne, Opcode::Nop, Opcode::Mul, Opcode::Div, Opcode::Mod, Opcode::Ldp, Opcode::Stp].contains(&opcode) {
+        return Err(format!("uses {opcode}, which '88 doesn't have"));
+    }
+    // there are no modifiers in '88, and without the source anything other than the one '88 implies is
+    // the only sign of an explicit modifier, check_warriors also finds the ones that match it
+    if instruction.modifier != Modifier::default_88_to_94(instruction.opcode, mode_a, mode_b) {
+        return Err(format!("uses the .{} modifier", instruction.modifier));
     }
-    let process_b = Process { team: 1, pointer: warrior_b_offset as usize };
+    for mode in [mode_a, mode_b] {
+        if ![AddressMode::Immediate, AddressMode::Direct, AddressMode::IndirectB, AddressMode::PreDecIndirectB].contains(&mode) {
+            return Err(format!("uses the {mode} address mode"));
+        }
+    }
+    match opcode {
+        Opcode::Dat => for mode in [mode_a, mode_b] {
+            if ![AddressMode::Immediate, AddressMode::PreDecIndirectB].contains(&mode) {
+                return Err(format!("uses the {mode} address mode with DAT"));
+            }
+        },
+        Opcode::Mov | Opcode::Add | Opcode::Sub | Opcode::Cmp | Opcode::Slt if mode_b == AddressMode::Immediate =>
+            return Err(format!("uses an immediate B field with {opcode}")),
+        Opcode::Jmp | Opcode::Jmz | Opcode::Jmn | Opcode::Djn | Opcode::Spl if mode_a == AddressMode::Immediate =>
+            return Err(format!("uses an immediate A field with {opcode}")),
+        _ => {}
+    }
+    return Ok(());
+}
 
-    return (core, vec![VecDeque::from([process_a]), VecDeque::from([process_b])]);
+fn check_warrior(warrior: &Warrior, config: &MarsConfig) -> Result<(), (Option<usize>, String)> { // why a warrior can't be loaded with config, and which instruction is wrong if it's just one
+    let length = warrior.program.instructions.len();
+    // a file of just comments and labels still parses, but leaves nothing to run
+    if length == 0 { return Err((None, "has no instructions".to_string())); }
+    if length > config.max_length {
+        return Err((None, format!("is {length} instructions long, but the max length is {}", config.max_length)));
+    }
+    for (i, instruction) in warrior.program.instructions.iter().enumerate() {
+        let opcode = translate_instruction(instruction.clone(), config.coresize).opcode;
+        if !config.allowed_opcodes.contains(&opcode) {
+            return Err((Some(i), format!("uses {opcode}, which isn't allowed in this match")));
+        }
+        if config.icws88 {
+            if let Err(error) = check_icws88_instruction(instruction, opcode) {
+                return Err((Some(i), format!("isn't valid ICWS'88, instruction {i} {error}")));
+            }
+        }
+    }
+    return Ok(());
 }
 
-fn calculate_source_and_dest_pointers(instruction: &Instruction, core: &Vec<Instruction>, process_pointer: usize, coresize: usize) -> (usize, usize) {
-    let source_instruction_pointer: usize; // pointer to first instruction of MOV; the instruction to be copied (relative address)
-    let dest_instruction_pointer: usize; // pointer to second instruction of MOV; the instruction to be copied to (relative address)
+fn find_explicit_modifier(source: &str) -> Option<(usize, String)> { // the first line giving an opcode a modifier, counting from 1, and the modifier
+    return source.lines().enumerate().find_map(|(i, line)| {
+        let code = line.split(';').next().unwrap_or_default();
+        let (start, end) = find_opcode(code)?;
+        let (_, modifier) = code[start..end].split_once('.')?;
+        return Some((i + 1, modifier.to_uppercase()));
+    });
+}
 
-    match instruction.field_a.address_mode {
-        AddressMode::Immediate =>
-            source_instruction_pointer = 0,
-        AddressMode::Direct =>
-            source_instruction_pointer = instruction.field_a.value,
-        AddressMode::IndirectA | AddressMode::PostIncIndirectA | AddressMode::PreDecIndirectA =>
-            source_instruction_pointer = core[(instruction.field_a.value + process_pointer) % coresize].field_a.value + instruction.field_a.value,
-        AddressMode::IndirectB | AddressMode::PostIncIndirectB | AddressMode::PreDecIndirectB =>
-            source_instruction_pointer = core[(instruction.field_a.value + process_pointer) % coresize].field_b.value + instruction.field_a.value
+pub fn check_warriors(warriors: &[ParsedWarrior], config: &MarsConfig) -> Vec<Diagnostic> { // the errors that stop each warrior from loading with config, at the line they're on
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+    for parsed in warriors {
+        // modifiers that match the ones '88 implies are gone once parsed, so they're looked for in the source
+        if let Some((line, modifier)) = find_explicit_modifier(&parsed.source).filter(|_| config.icws88) {
+            let message = format!("the warrior isn't valid ICWS'88, it uses the .{modifier} modifier");
+            diagnostics.push(Diagnostic { severity: Severity::Error, path: Some(parsed.path.clone()), line: Some(line), message });
+            continue;
+        }
+        if let Err((instruction, message)) = check_warrior(&parsed.warrior, config) {
+            let line = instruction.and_then(|i| instruction_lines(&parsed.source, parsed.warrior.program.instructions.len())[i]);
+            diagnostics.push(Diagnostic { severity: Severity::Error, path: Some(parsed.path.clone()), line, message: format!("the warrior {message}") });
+        }
+    }
+    return diagnostics;
+}
+
+pub(crate) struct Placement {
+    pub core: Vec<Instruction>,
+    pub owners: Vec<Option<Owner>>, // the team each warrior's cells belong to
+    pub teams_process_queues: Vec<VecDeque<Process>>,
+    pub start_positions: Vec<usize>, // the address each warrior's first instruction was loaded at
+}
+
+pub(crate) fn place_warriors(warriors: &[Warrior], config: &MarsConfig, rng: &mut impl Rng) -> Result<Placement, String> {
+    let coresize = config.coresize;
+    if warriors.is_empty() { return Err("No warriors to place".to_string()); }
+    if warriors.len() > u8::MAX as usize { return Err(format!("Too many warriors, the maximum is {}", u8::MAX)); }
+
+    for (team, warrior) in warriors.iter().enumerate() {
+        if let Err((_, message)) = check_warrior(warrior, config) {
+            return Err(format!("Warrior {team} ({}) {message}", warrior.metadata.name.clone().unwrap_or_default()));
+        }
+    }
+
+    // two warriors fit if their starts are at least min_distance apart and neither runs into the other
+    let lengths: Vec<usize> = warriors.iter().map(|warrior| warrior.program.instructions.len()).collect();
+    let fits = |team: usize, start: usize, start_positions: &[Option<usize>]| -> bool {
+        start_positions.iter().enumerate().all(|(other, other_start)| match other_start {
+            Some(other_start) => {
+                let distance = minus_mod(*other_start, start, coresize); // from this warrior's start to the other's
+                distance >= max(config.min_distance, lengths[team]) && coresize - distance >= max(config.min_distance, lengths[other])
+            }
+            None => true,
+        })
+    };
+
+    if let Some(team) = (warriors.len()..config.positions.len()).find(|team| config.positions[*team].is_some()) {
+        return Err(format!("Warrior {team} was given a position, but there are only {} warriors", warriors.len()));
+    }
+    let mut start_positions: Vec<Option<usize>> = vec![None; warriors.len()];
+    for team in 0..warriors.len() {
+        if let Some(position) = config.positions.get(team).copied().flatten() {
+            if position >= coresize { return Err(format!("Warrior {team}'s position {position} is outside the core")); }
+            if !fits(team, position, &start_positions) {
+                return Err(format!("Warrior {team} at {position} overlaps or is within {} cells of another warrior", config.min_distance));
+            }
+            start_positions[team] = Some(position);
+        }
+    }
+
+    // the first warrior starts at 0 unless it was given a position or 0 is taken
+    if start_positions[0].is_none() && fits(0, 0, &start_positions) { start_positions[0] = Some(0); }
+
+    // the rest are placed at a random address out of every one that fits
+    for team in 0..warriors.len() {
+        if start_positions[team].is_some() { continue; }
+        let candidates: Vec<usize> = (0..coresize).filter(|start| fits(team, *start, &start_positions)).collect();
+        if candidates.is_empty() {
+            return Err(format!("No room for warrior {team} at least {} cells away from the others", config.min_distance));
+        }
+        start_positions[team] = Some(candidates[rng.random_range(0..candidates.len())]);
+    }
+    let start_positions: Vec<usize> = start_positions.into_iter().flatten().collect();
+
+    let mut core = vec![config.default_instruction; coresize];
+    let mut owners: Vec<Option<Owner>> = vec![None; coresize];
+    let mut teams_process_queues: Vec<VecDeque<Process>> = Vec::new();
+    for (team, warrior) in warriors.iter().enumerate() {
+        let start = start_positions[team];
+        for (i, instruction) in warrior.program.instructions.iter().enumerate() {
+            core[(start + i) % coresize] = translate_instruction(instruction.clone(), coresize);
+            owners[(start + i) % coresize] = Some(Owner { team: team as u8, cycle: 0, writer: None });
+        }
+        let origin: usize = match warrior.program.origin {Some(n) => n as usize, None => 0};
+        teams_process_queues.push(VecDeque::from([Process { team: team as u8, pointer: (start + origin) % coresize }]));
     }
 
-    match instruction.field_b.address_mode {
+    return Ok(Placement { core, owners, teams_process_queues, start_positions });
+}
+
+fn fold(pointer: usize, limit: usize, coresize: usize) -> usize { // folds a relative pointer into the window of limit cells around the process, like pMARS
+    let folded = (pointer % coresize) % limit;
+    return if folded > limit / 2 { folded + coresize - limit } else { folded };
+}
+
+// works out where a field points relative to the process, like the ICWS'94 reference MARS: the field, the cell
+// holding an indirect pointer and the final address are all folded by limit, so reads and writes each follow their own
+fn field_pointer(field: &Field, core: &[Instruction], process_pointer: usize, coresize: usize, limit: usize) -> usize {
+    let pointer = fold(field.value, limit, coresize);
+    let pointer_cell = (pointer + process_pointer) % coresize;
+    return match field.address_mode {
         AddressMode::Immediate =>
-            dest_instruction_pointer = 0,
+            0,
         AddressMode::Direct =>
-            dest_instruction_pointer = instruction.field_b.value,
+            pointer,
         AddressMode::IndirectA | AddressMode::PostIncIndirectA | AddressMode::PreDecIndirectA =>
-            dest_instruction_pointer = core[(instruction.field_b.value + process_pointer) % coresize].field_a.value + instruction.field_b.value,
+            fold(pointer + core[pointer_cell].field_a.value, limit, coresize),
         AddressMode::IndirectB | AddressMode::PostIncIndirectB | AddressMode::PreDecIndirectB =>
-            dest_instruction_pointer = core[(instruction.field_b.value + process_pointer) % coresize].field_b.value + instruction.field_b.value
-    }
-
-    return (source_instruction_pointer, dest_instruction_pointer);
+            fold(pointer + core[pointer_cell].field_b.value, limit, coresize),
+    };
 }
 
-fn step_process(core: &mut Vec<Instruction>, coresize: usize, process_queue: &mut VecDeque<Process>) { // steps with the first process in the process queue
-    let process = process_queue[0];
-    let instruction = core[process.pointer].clone();
-    let mut dead: bool = false;
-    let mut step: bool = true;
+fn calculate_source_and_dest_pointers(instruction: &Instruction, core: &[Instruction], process_pointer: usize, coresize: usize, read_limit: usize, write_limit: usize) -> (usize, usize, usize) {
+    let source_instruction_pointer = field_pointer(&instruction.field_a, core, process_pointer, coresize, read_limit); // the instruction to be read from (relative address)
+    let dest_read_instruction_pointer = field_pointer(&instruction.field_b, core, process_pointer, coresize, read_limit); // the instruction to be tested (relative address)
+    let dest_instruction_pointer = field_pointer(&instruction.field_b, core, process_pointer, coresize, write_limit); // the instruction to be written to (relative address)
+    return (source_instruction_pointer, dest_read_instruction_pointer, dest_instruction_pointer);
+}
 
+fn predecrement(instruction: &Instruction, core: &mut [Instruction], field_a_pointer: usize, field_b_pointer: usize, coresize: usize) { // decrements the indirect pointers of any predecrement fields
     // process predecrements for field a
     if instruction.field_a.address_mode == AddressMode::PreDecIndirectA {
-        decrement_mod(&mut core[(instruction.field_a.value + process.pointer) % coresize].field_a.value, coresize);
+        decrement_mod(&mut core[field_a_pointer].field_a.value, coresize);
     } else if instruction.field_a.address_mode == AddressMode::PreDecIndirectB {
-        decrement_mod(&mut core[(instruction.field_a.value + process.pointer) % coresize].field_b.value, coresize);
+        decrement_mod(&mut core[field_a_pointer].field_b.value, coresize);
     }
 
     // process predecrements for field b
     if instruction.field_b.address_mode == AddressMode::PreDecIndirectA {
-        decrement_mod(&mut core[(instruction.field_b.value + process.pointer) % coresize].field_a.value, coresize);
+        decrement_mod(&mut core[field_b_pointer].field_a.value, coresize);
     } else if instruction.field_b.address_mode == AddressMode::PreDecIndirectB {
-        decrement_mod(&mut core[(instruction.field_b.value + process.pointer) % coresize].field_b.value, coresize);
+        decrement_mod(&mut core[field_b_pointer].field_b.value, coresize);
     }
+}
 
-    let (source_instruction_pointer, dest_instruction_pointer) = calculate_source_and_dest_pointers(&instruction, &core, process.pointer, coresize);
-    let source = (source_instruction_pointer + process.pointer) % coresize;
-    let destination = (dest_instruction_pointer + process.pointer) % coresize;
-
-    // big if block for all the opcodes
-    match instruction.opcode {
-        Opcode::Dat => { // kills the first process (this process)
-            dead = true;
-        }
-        Opcode::Mov => { // moves instruction/values specified by A field to instruction specified by B field
-            match instruction.modifier {
-                Modifier::A =>
-                    core[destination].field_a.value = core[source].field_a.value,
-                Modifier::B =>
-                    core[destination].field_b.value = core[source].field_b.value,
-                Modifier::AB =>
-                    core[destination].field_b.value = core[source].field_a.value,
-                Modifier::BA =>
-                    core[destination].field_a.value = core[source].field_b.value,
-                Modifier::F => {
-                    core[destination].field_a.value = core[source].field_a.value;
-                    core[destination].field_b.value = core[source].field_b.value; },
-                Modifier::X => {
-                    core[destination].field_b.value = core[source].field_a.value;
-                    core[destination].field_a.value = core[source].field_b.value; },
-                Modifier::I =>
-                    core[destination] = core[source],
-            }
+impl Mars {
+    pub fn targets(&self, pointer: usize) -> (usize, usize) { // the addresses the A and B fields of the instruction at pointer would resolve to if a process executed it now
+        let coresize = self.config.coresize;
+        let (read_limit, write_limit) = (self.config.read_limit, self.config.write_limit);
+        let instruction = self.core[pointer];
+        let is_predecrement = |field: &Field| matches!(field.address_mode, AddressMode::PreDecIndirectA | AddressMode::PreDecIndirectB);
+        // predecrements change the pointers before they're followed, so they're applied to a copy of the core
+        let mut core = Cow::Borrowed(&self.core[..]);
+        if is_predecrement(&instruction.field_a) || is_predecrement(&instruction.field_b) {
+            let field_a_pointer = (fold(instruction.field_a.value, write_limit, coresize) + pointer) % coresize;
+            let field_b_pointer = (fold(instruction.field_b.value, write_limit, coresize) + pointer) % coresize;
+            predecrement(&instruction, core.to_mut(), field_a_pointer, field_b_pointer, coresize);
         }
-        Opcode::Add => { // adds number(s) specified by A field to instruction specified by B field
-            match instruction.modifier {
-                Modifier::A => {
-                    core[destination].field_a.value += core[source].field_a.value;
-                    core[destination].field_a.value %= coresize;
-                }
-                Modifier::B => {
-                    core[destination].field_b.value += core[source].field_b.value;
-                    core[destination].field_b.value %= coresize;}
-                Modifier::AB => {
-                    core[destination].field_b.value += core[source].field_a.value;
-                    core[destination].field_b.value %= coresize;
-                }
-                Modifier::BA => {
-                    core[destination].field_a.value += core[source].field_b.value;
-                    core[destination].field_a.value %= coresize;
-                }
-                Modifier::F | Modifier::I => {
-                    core[destination].field_a.value += core[source].field_a.value;
-                    core[destination].field_a.value %= coresize;
-                    core[destination].field_b.value += core[source].field_b.value;
-                    core[destination].field_b.value %= coresize;
-                }
-                Modifier::X => {
-                    core[destination].field_a.value += core[source].field_b.value;
-                    core[destination].field_a.value %= coresize;
-                    core[destination].field_b.value += core[source].field_a.value;
-                    core[destination].field_b.value %= coresize;
-                }
-            }
-        }
-        Opcode::Sub => { // subtracts number(s) specified by A field from instruction specified by B field
-            match instruction.modifier {
-                Modifier::A => {
-                    core[destination].field_a.value = minus_mod(core[destination].field_a.value, core[source].field_a.value, coresize);
-                }
-                Modifier::B => {
-                    core[destination].field_b.value = minus_mod(core[destination].field_b.value, core[source].field_b.value, coresize);
-                }
-                Modifier::AB => {
-                    core[destination].field_b.value = minus_mod(core[destination].field_b.value, core[source].field_a.value, coresize);
-                }
-                Modifier::BA => {
-                    core[destination].field_a.value = minus_mod(core[destination].field_a.value, core[source].field_b.value, coresize);
-                }
-                Modifier::F | Modifier::I => {
-                    core[destination].field_a.value = minus_mod(core[destination].field_a.value, core[source].field_a.value, coresize);
-                    core[destination].field_b.value = minus_mod(core[destination].field_b.value, core[source].field_b.value, coresize);
-                }
-                Modifier::X => {
-                    core[destination].field_a.value = minus_mod(core[destination].field_a.value, core[source].field_b.value, coresize);
-                    core[destination].field_b.value = minus_mod(core[destination].field_b.value, core[source].field_a.value, coresize);
-                }
+        let (source, _, destination) = calculate_source_and_dest_pointers(&instruction, &core, pointer, coresize, read_limit, write_limit);
+        return ((source + pointer) % coresize, (destination + pointer) % coresize);
+    }
+
+    #[allow(clippy::manual_checked_ops)] // DIV and MOD kill the process on a zero divisor, so it's checked in every arm
+    pub(crate) fn step_process(&mut self) -> StepRecord { // steps with the first process in the process queue of the team whose turn it is
+        let coresize = self.config.coresize;
+        let (read_limit, write_limit) = (self.config.read_limit, self.config.write_limit);
+        let icws88 = self.config.icws88;
+        let core = &mut self.core;
+        let process_queue = &mut self.teams_process_queues[self.turn];
+        let queue_length = process_queue.len();
+        let process = process_queue[0];
+        let instruction = core[process.pointer];
+        let mut dead: bool = false;
+        let mut step: bool = true;
+        let mut saved_pspace_cell: Option<(usize, usize)> = None;
+        let mut new_process: Option<Process> = None; // the process SPL splits off, which is queued behind this one
+        let mut divided: bool = false; // whether DIV or MOD divided, as a division by zero leaves their destination alone
+
+        // the cells holding indirect pointers, which the increments and decrements write to
+        let field_a_pointer_cell = fold(instruction.field_a.value, write_limit, coresize);
+        let field_b_pointer_cell = fold(instruction.field_b.value, write_limit, coresize);
+        let field_a_pointer = (field_a_pointer_cell + process.pointer) % coresize;
+        let field_b_pointer = (field_b_pointer_cell + process.pointer) % coresize;
+        let saved_field_a_pointer = (field_a_pointer, core[field_a_pointer]);
+        let saved_field_b_pointer = (field_b_pointer, core[field_b_pointer]);
+
+        predecrement(&instruction, core, field_a_pointer, field_b_pointer, coresize);
+
+        let (source_instruction_pointer, dest_read_instruction_pointer, dest_instruction_pointer) = calculate_source_and_dest_pointers(&instruction, core, process.pointer, coresize, read_limit, write_limit);
+        let source = (source_instruction_pointer + process.pointer) % coresize;
+        let destination_read = (dest_read_instruction_pointer + process.pointer) % coresize;
+        let destination = (dest_instruction_pointer + process.pointer) % coresize;
+        // together with the pointer cells saved above, these are the only cells a step can write to
+        let saved_destination = (destination, core[destination]);
+
+        // the cells read by this step are any indirect pointers, then the A and B targets of the opcodes that read them
+        let indirect_pointer = |field: &Field| match field.address_mode {
+            AddressMode::Immediate | AddressMode::Direct => None,
+            _ => Some((fold(field.value, read_limit, coresize) + process.pointer) % coresize),
+        };
+        let opcode = instruction.opcode;
+        let reads_a_target = [Opcode::Mov, Opcode::Add, Opcode::Sub, Opcode::Mul, Opcode::Div, Opcode::Mod, Opcode::Cmp, Opcode::Seq, Opcode::Sne, Opcode::Slt, Opcode::Ldp, Opcode::Stp].contains(&opcode);
+        let reads_b_target = [Opcode::Add, Opcode::Sub, Opcode::Mul, Opcode::Div, Opcode::Mod, Opcode::Djn].contains(&opcode);
+        let tests_b_target = [Opcode::Cmp, Opcode::Seq, Opcode::Sne, Opcode::Slt, Opcode::Jmz, Opcode::Jmn, Opcode::Stp].contains(&opcode);
+        let read_cells = [
+            indirect_pointer(&instruction.field_a),
+            indirect_pointer(&instruction.field_b),
+            Some(source).filter(|_| reads_a_target && instruction.field_a.address_mode != AddressMode::Immediate),
+            if instruction.field_b.address_mode == AddressMode::Immediate { None }
+            else if reads_b_target { Some(destination) }
+            else if tests_b_target { Some(destination_read) }
+            else { None },
+        ];
+
+        // big if block for all the opcodes
+        match instruction.opcode {
+            Opcode::Dat => { // kills the first process (this process)
+                dead = true;
             }
-        }
-        Opcode::Mul => { // multiplies number(s) specified by A field into instruction specified by B field
-            match instruction.modifier {
-                Modifier::A => {
-                    core[destination].field_a.value *= core[source].field_a.value;
-                    core[destination].field_a.value %= coresize;
-                },
-                Modifier::B => {
-                    core[destination].field_b.value *= core[source].field_b.value;
-                    core[destination].field_b.value %= coresize;
-
-                },
-                Modifier::AB => {
-                    core[destination].field_b.value *= core[source].field_a.value;
-                    core[destination].field_b.value %= coresize;
-
-                },
-                Modifier::BA => {
-                    core[destination].field_a.value *= core[source].field_b.value;
-                    core[destination].field_a.value %= coresize;
-                },
-                Modifier::F | Modifier::I => {
-                    core[destination].field_a.value *= core[source].field_a.value;
-                    core[destination].field_a.value %= coresize;
-                    core[destination].field_b.value *= core[source].field_b.value;
-                    core[destination].field_b.value %= coresize;
-                },
-                Modifier::X => {
-                    core[destination].field_b.value *= core[source].field_a.value;
-                    core[destination].field_b.value %= coresize;
-                    core[destination].field_a.value *= core[source].field_b.value;
-                    core[destination].field_a.value %= coresize;
+            Opcode::Mov => { // moves instruction/values specified by A field to instruction specified by B field
+                match instruction.modifier {
+                    Modifier::A =>
+                        core[destination].field_a.value = core[source].field_a.value,
+                    Modifier::B =>
+                        core[destination].field_b.value = core[source].field_b.value,
+                    Modifier::AB =>
+                        core[destination].field_b.value = core[source].field_a.value,
+                    Modifier::BA =>
+                        core[destination].field_a.value = core[source].field_b.value,
+                    Modifier::F => {
+                        core[destination].field_a.value = core[source].field_a.value;
+                        core[destination].field_b.value = core[source].field_b.value; },
+                    Modifier::X => {
+                        core[destination].field_b.value = core[source].field_a.value;
+                        core[destination].field_a.value = core[source].field_b.value; },
+                    Modifier::I =>
+                        core[destination] = core[source],
                 }
             }
-        }
-        Opcode::Div => { // divides instruction specified by B field by number(s) specified by A field
-            match instruction.modifier {
-                Modifier::A => {
-                    if core[source].field_a.value == 0 { dead = true }
-                    else {
-                        core[destination].field_a.value /= core[source].field_a.value;
-                    }
-                },
-                Modifier::B => {
-                    if core[source].field_b.value == 0 { dead = true }
-                    else {
-                        core[destination].field_b.value /= core[source].field_b.value;
+            Opcode::Add => { // adds number(s) specified by A field to instruction specified by B field
+                match instruction.modifier {
+                    Modifier::A => {
+                        core[destination].field_a.value += core[source].field_a.value;
+                        core[destination].field_a.value %= coresize;
                     }
-                },
-                Modifier::AB => {
-                    if core[source].field_a.value == 0 { dead = true }
-                    else {
-                        core[destination].field_b.value /= core[source].field_a.value;
+                    Modifier::B => {
+                        core[destination].field_b.value += core[source].field_b.value;
+                        core[destination].field_b.value %= coresize;}
+                    Modifier::AB => {
+                        core[destination].field_b.value += core[source].field_a.value;
+                        core[destination].field_b.value %= coresize;
                     }
-                },
-                Modifier::BA => {
-                    if core[source].field_b.value == 0 { dead = true }
-                    else {
-                        core[destination].field_a.value /= core[source].field_b.value;
+                    Modifier::BA => {
+                        core[destination].field_a.value += core[source].field_b.value;
+                        core[destination].field_a.value %= coresize;
                     }
-                },
-                Modifier::F | Modifier::I => {
-                    if core[source].field_a.value == 0 || core[source].field_b.value == 0 { dead = true }
-                    else {
-                        core[destination].field_a.value /= core[source].field_a.value;
-                        core[destination].field_b.value /= core[source].field_b.value;
+                    Modifier::F | Modifier::I => {
+                        core[destination].field_a.value += core[source].field_a.value;
+                        core[destination].field_a.value %= coresize;
+                        core[destination].field_b.value += core[source].field_b.value;
+                        core[destination].field_b.value %= coresize;
                     }
-                },
-                Modifier::X => {
-                    if core[source].field_a.value == 0 || core[source].field_b.value == 0 { dead = true }
-                    else {
-                        core[destination].field_b.value /= core[source].field_a.value;
-                        core[destination].field_a.value /= core[source].field_b.value;
+                    Modifier::X => {
+                        core[destination].field_a.value += core[source].field_b.value;
+                        core[destination].field_a.value %= coresize;
+                        core[destination].field_b.value += core[source].field_a.value;
+                        core[destination].field_b.value %= coresize;
                     }
                 }
             }
-        }
-        Opcode::Mod => { // mods instruction specified by B field by number(s) specified by A field
-            match instruction.modifier {
-                Modifier::A => {
-                    if core[source].field_a.value == 0 { dead = true }
-                    else {
-                        core[destination].field_a.value %= core[source].field_a.value;
+            Opcode::Sub => { // subtracts number(s) specified by A field from instruction specified by B field
+                match instruction.modifier {
+                    Modifier::A => {
+                        core[destination].field_a.value = minus_mod(core[destination].field_a.value, core[source].field_a.value, coresize);
                     }
-                },
-                Modifier::B => {
-                    if core[source].field_b.value == 0 { dead = true }
-                    else {
-                        core[destination].field_b.value %= core[source].field_b.value;
+                    Modifier::B => {
+                        core[destination].field_b.value = minus_mod(core[destination].field_b.value, core[source].field_b.value, coresize);
                     }
-                },
-                Modifier::AB => {
-                    if core[source].field_a.value == 0 { dead = true }
-                    else {
-                        core[destination].field_b.value %= core[source].field_a.value;
+                    Modifier::AB => {
+                        core[destination].field_b.value = minus_mod(core[destination].field_b.value, core[source].field_a.value, coresize);
                     }
-                },
-                Modifier::BA => {
-                    if core[source].field_b.value == 0 { dead = true }
-                    else {
-                        core[destination].field_a.value %= core[source].field_b.value;
+                    Modifier::BA => {
+                        core[destination].field_a.value = minus_mod(core[destination].field_a.value, core[source].field_b.value, coresize);
                     }
-                },
-                Modifier::F | Modifier::I => {
-                    if core[source].field_a.value == 0 || core[source].field_b.value == 0 { dead = true }
-                    else {
-                        core[destination].field_a.value %= core[source].field_a.value;
-                        core[destination].field_b.value %= core[source].field_b.value;
+                    Modifier::F | Modifier::I => {
+                        core[destination].field_a.value = minus_mod(core[destination].field_a.value, core[source].field_a.value, coresize);
+                        core[destination].field_b.value = minus_mod(core[destination].field_b.value, core[source].field_b.value, coresize);
                     }
-                },
-                Modifier::X => {
-                    if core[source].field_a.value == 0 || core[source].field_b.value == 0 { dead = true }
-                    else {
-                        core[destination].field_b.value %= core[source].field_a.value;
-                        core[destination].field_a.value %= core[source].field_b.value;
+                    Modifier::X => {
+                        core[destination].field_a.value = minus_mod(core[destination].field_a.value, core[source].field_b.value, coresize);
+                        core[destination].field_b.value = minus_mod(core[destination].field_b.value, core[source].field_a.value, coresize);
                     }
                 }
             }
-        }
-        Opcode::Jmp => { // jumps to address specified by A field
-            process_queue[0].pointer = source;
-            step = false;
-        }
-        Opcode::Jmz => { // jumps to address specified by A field if field(s) specified by B field equals 0
-            match instruction.modifier {
-                Modifier::A | Modifier::BA => {
-                    if core[destination].field_a.value == 0 {
-                        process_queue[0].pointer = source;
-                        step = false;
-                    }
-                },
-                Modifier::B | Modifier::AB => {
-                    if core[destination].field_b.value == 0 {
-                        process_queue[0].pointer = source;
-                        step = false;
-                    }
-                },
-                Modifier::X | Modifier::F | Modifier::I => {
-                    if core[destination].field_a.value == 0 && core[destination].field_b.value == 0 {
-                        process_queue[0].pointer = source;
-                        step = false;
+            Opcode::Mul => { // multiplies number(s) specified by A field into instruction specified by B field
+                match instruction.modifier {
+                    Modifier::A => {
+                        core[destination].field_a.value *= core[source].field_a.value;
+                        core[destination].field_a.value %= coresize;
+                    },
+                    Modifier::B => {
+                        core[destination].field_b.value *= core[source].field_b.value;
+                        core[destination].field_b.value %= coresize;
+
+                    },
+                    Modifier::AB => {
+                        core[destination].field_b.value *= core[source].field_a.value;
+                        core[destination].field_b.value %= coresize;
+
+                    },
+                    Modifier::BA => {
+                        core[destination].field_a.value *= core[source].field_b.value;
+                        core[destination].field_a.value %= coresize;
+                    },
+                    Modifier::F | Modifier::I => {
+                        core[destination].field_a.value *= core[source].field_a.value;
+                        core[destination].field_a.value %= coresize;
+                        core[destination].field_b.value *= core[source].field_b.value;
+                        core[destination].field_b.value %= coresize;
+                    },
+                    Modifier::X => {
+                        core[destination].field_b.value *= core[source].field_a.value;
+                        core[destination].field_b.value %= coresize;
+                        core[destination].field_a.value *= core[source].field_b.value;
+                        core[destination].field_a.value %= coresize;
                     }
-                },
+                }
             }
-        }
-        Opcode::Jmn => { // jumps to address specified by A field if field(s) specified by B field are not equal to 0
-            match instruction.modifier {
-                Modifier::A | Modifier::BA => {
-                    if core[destination].field_a.value != 0 {
-                        process_queue[0].pointer = source;
-                        step = false;
-                    }
-                },
-                Modifier::B | Modifier::AB => {
-                    if core[destination].field_b.value != 0 {
-                        process_queue[0].pointer = source;
-                        step = false;
-                    }
-                },
-                Modifier::X | Modifier::F | Modifier::I => {
-                    if core[destination].field_a.value != 0 || core[destination].field_b.value != 0 {
-                        process_queue[0].pointer = source;
-                        step = false;
+            Opcode::Div => { // divides instruction specified by B field by number(s) specified by A field
+                match instruction.modifier {
+                    Modifier::A => {
+                        if core[source].field_a.value == 0 { dead = true }
+                        else {
+                            core[destination].field_a.value /= core[source].field_a.value;
+                            divided = true;
+                        }
+                    },
+                    Modifier::B => {
+                        if core[source].field_b.value == 0 { dead = true }
+                        else {
+                            core[destination].field_b.value /= core[source].field_b.value;
+                            divided = true;
+                        }
+                    },
+                    Modifier::AB => {
+                        if core[source].field_a.value == 0 { dead = true }
+                        else {
+                            core[destination].field_b.value /= core[source].field_a.value;
+                            divided = true;
+                        }
+                    },
+                    Modifier::BA => {
+                        if core[source].field_b.value == 0 { dead = true }
+                        else {
+                            core[destination].field_a.value /= core[source].field_b.value;
+                            divided = true;
+                        }
+                    },
+                    Modifier::F | Modifier::I => {
+                        if core[source].field_a.value == 0 || core[source].field_b.value == 0 { dead = true }
+                        else {
+                            core[destination].field_a.value /= core[source].field_a.value;
+                            core[destination].field_b.value /= core[source].field_b.value;
+                            divided = true;
+                        }
+                    },
+                    Modifier::X => {
+                        if core[source].field_a.value == 0 || core[source].field_b.value == 0 { dead = true }
+                        else {
+                            core[destination].field_b.value /= core[source].field_a.value;
+                            core[destination].field_a.value /= core[source].field_b.value;
+                            divided = true;
+                        }
                     }
-                },
+                }
             }
-        }
-        Opcode::Djn => { // decrements field specified by B field, then JMZs
-            match instruction.modifier {
-                Modifier::A | Modifier::BA => {
-                    decrement_mod(&mut core[destination].field_a.value, coresize);
-                    if core[destination].field_a.value != 0 {
-                        process_queue[0].pointer = source;
-                        step = false;
-                    }
-                },
-                Modifier::B | Modifier::AB => {
-                    decrement_mod(&mut core[destination].field_b.value, coresize);
-                    if core[destination].field_b.value != 0 {
-                        process_queue[0].pointer = source;
-                        step = false;
-                    }
-                },
-                Modifier::X | Modifier::F | Modifier::I => {
-                    decrement_mod(&mut core[destination].field_a.value, coresize);
-                    decrement_mod(&mut core[destination].field_b.value, coresize);
-                    if core[destination].field_a.value != 0 || core[destination].field_b.value != 0 {
-                        process_queue[0].pointer = source;
-                        step = false;
+            Opcode::Mod => { // mods instruction specified by B field by number(s) specified by A field
+                match instruction.modifier {
+                    Modifier::A => {
+                        if core[source].field_a.value == 0 { dead = true }
+                        else {
+                            core[destination].field_a.value %= core[source].field_a.value;
+                            divided = true;
+                        }
+                    },
+                    Modifier::B => {
+                        if core[source].field_b.value == 0 { dead = true }
+                        else {
+                            core[destination].field_b.value %= core[source].field_b.value;
+                            divided = true;
+                        }
+                    },
+                    Modifier::AB => {
+                        if core[source].field_a.value == 0 { dead = true }
+                        else {
+                            core[destination].field_b.value %= core[source].field_a.value;
+                            divided = true;
+                        }
+                    },
+                    Modifier::BA => {
+                        if core[source].field_b.value == 0 { dead = true }
+                        else {
+                            core[destination].field_a.value %= core[source].field_b.value;
+                            divided = true;
+                        }
+                    },
+                    Modifier::F | Modifier::I => {
+                        if core[source].field_a.value == 0 || core[source].field_b.value == 0 { dead = true }
+                        else {
+                            core[destination].field_a.value %= core[source].field_a.value;
+                            core[destination].field_b.value %= core[source].field_b.value;
+                            divided = true;
+                        }
+                    },
+                    Modifier::X => {
+                        if core[source].field_a.value == 0 || core[source].field_b.value == 0 { dead = true }
+                        else {
+                            core[destination].field_b.value %= core[source].field_a.value;
+                            core[destination].field_a.value %= core[source].field_b.value;
+                            divided = true;
+                        }
                     }
-                },
-            }
-        }
-        Opcode::Spl => {
-            let new_process = Process {
-                team: process.team,
-                pointer: source
-            };
-            process_queue.push_front(new_process); // push_front is used because rotate_left will be run right after, effectively being a push_back with no rotation
-            step = false; // if this was true, the new process would be stepped instead of the current one
-            increment_mod(&mut process_queue[1].pointer, coresize);
-        }
-        Opcode::Seq | Opcode::Cmp => { // skips next instruction if instructions specified by A and B field are equal
-            let mut skip: bool = false;
-
-            match instruction.modifier {
-                Modifier::A =>
-                    if core[destination].field_a.value == core[source].field_a.value { skip = true; },
-                Modifier::B =>
-                    if core[destination].field_b.value == core[source].field_b.value { skip = true; },
-                Modifier::AB =>
-                    if core[destination].field_b.value == core[source].field_a.value { skip = true; },
-                Modifier::BA =>
-                    if core[destination].field_a.value == core[source].field_b.value { skip = true; },
-                Modifier::F =>
-                    if core[destination].field_a.value == core[source].field_a.value && core[destination].field_b.value == core[source].field_b.value { skip = true; },
-                Modifier::X =>
-                    if core[destination].field_b.value == core[source].field_a.value && core[destination].field_a.value == core[source].field_b.value { skip = true; },
-                Modifier::I =>
-                    if core[destination] == core[source] { skip = true; },
+                }
             }
-
-            if skip {
-                process_queue[0].pointer += 2;
+            Opcode::Jmp => { // jumps to address specified by A field
+                process_queue[0].pointer = source;
                 step = false;
             }
-        }
-        Opcode::Sne => { // skips next instruction if instructions specified by A and B field are not equal
-            let mut skip: bool = false;
-
-            match instruction.modifier {
-                Modifier::A =>
-                    if core[destination].field_a.value != core[source].field_a.value { skip = true; },
-                Modifier::B =>
-                    if core[destination].field_b.value != core[source].field_b.value { skip = true; },
-                Modifier::AB =>
-                    if core[destination].field_b.value != core[source].field_a.value { skip = true; },
-                Modifier::BA =>
-                    if core[destination].field_a.value != core[source].field_b.value { skip = true; },
-                Modifier::F =>
-                    if core[destination].field_a.value != core[source].field_a.value || core[destination].field_b.value != core[source].field_b.value { skip = true; },
-                Modifier::X =>
-                    if core[destination].field_b.value != core[source].field_a.value || core[destination].field_a.value != core[source].field_b.value { skip = true; },
-                Modifier::I =>
-                    if core[destination] != core[source] { skip = true; },
+            Opcode::Jmz => { // jumps to address specified by A field if field(s) specified by B field equals 0
+                match instruction.modifier {
+                    Modifier::A | Modifier::BA => {
+                        if core[destination_read].field_a.value == 0 {
+                            process_queue[0].pointer = source;
+                            step = false;
+                        }
+                    },
+                    Modifier::B | Modifier::AB => {
+                        if core[destination_read].field_b.value == 0 {
+                            process_queue[0].pointer = source;
+                            step = false;
+                        }
+                    },
+                    Modifier::X | Modifier::F | Modifier::I => {
+                        if core[destination_read].field_a.value == 0 && core[destination_read].field_b.value == 0 {
+                            process_queue[0].pointer = source;
+                            step = false;
+                        }
+                    },
+                }
+            }
+            Opcode::Jmn => { // jumps to address specified by A field if field(s) specified by B field are not equal to 0
+                match instruction.modifier {
+                    Modifier::A | Modifier::BA => {
+                        if core[destination_read].field_a.value != 0 {
+                            process_queue[0].pointer = source;
+                            step = false;
+                        }
+                    },
+                    Modifier::B | Modifier::AB => {
+                        if core[destination_read].field_b.value != 0 {
+                            process_queue[0].pointer = source;
+                            step = false;
+                        }
+                    },
+                    Modifier::X | Modifier::F | Modifier::I => {
+                        if core[destination_read].field_a.value != 0 || core[destination_read].field_b.value != 0 {
+                            process_queue[0].pointer = source;
+                            step = false;
+                        }
+                    },
+                }
             }
+            Opcode::Djn => { // decrements field specified by B field, then JMZs
+                match instruction.modifier {
+                    Modifier::A | Modifier::BA => {
+                        decrement_mod(&mut core[destination].field_a.value, coresize);
+                        if core[destination].field_a.value != 0 {
+                            process_queue[0].pointer = source;
+                            step = false;
+                        }
+                    },
+                    Modifier::B | Modifier::AB => {
+                        decrement_mod(&mut core[destination].field_b.value, coresize);
+                        if core[destination].field_b.value != 0 {
+                            process_queue[0].pointer = source;
+                            step = false;
+                        }
+                    },
+                    Modifier::X | Modifier::F | Modifier::I => {
+                        decrement_mod(&mut core[destination].field_a.value, coresize);
+                        decrement_mod(&mut core[destination].field_b.value, coresize);
+                        if core[destination].field_a.value != 0 || core[destination].field_b.value != 0 {
+                            process_queue[0].pointer = source;
+                            step = false;
+                        }
+                    },
+                }
+            }
+            Opcode::Spl => { // splits off a new process at address specified by A field, unless the team is at the process limit
+                if process_queue.len() < self.config.max_processes {
+                    new_process = Some(Process {
+                        team: process.team,
+                        pointer: source
+                    });
+                }
+            }
+            Opcode::Seq | Opcode::Cmp => { // skips next instruction if instructions specified by A and B field are equal
+                let mut skip: bool = false;
 
-            if skip {
-                process_queue[0].pointer += 2;
-                step = false;
+                match instruction.modifier {
+                    Modifier::A =>
+                        if core[destination_read].field_a.value == core[source].field_a.value { skip = true; },
+                    Modifier::B =>
+                        if core[destination_read].field_b.value == core[source].field_b.value { skip = true; },
+                    Modifier::AB =>
+                        if core[destination_read].field_b.value == core[source].field_a.value { skip = true; },
+                    Modifier::BA =>
+                        if core[destination_read].field_a.value == core[source].field_b.value { skip = true; },
+                    Modifier::F =>
+                        if core[destination_read].field_a.value == core[source].field_a.value && core[destination_read].field_b.value == core[source].field_b.value { skip = true; },
+                    Modifier::X =>
+                        if core[destination_read].field_b.value == core[source].field_a.value && core[destination_read].field_a.value == core[source].field_b.value { skip = true; },
+                    Modifier::I if icws88 => // '88 has no modifiers, so only the opcodes, address modes and values are compared
+                        if core[destination_read].opcode == core[source].opcode && core[destination_read].field_a == core[source].field_a && core[destination_read].field_b == core[source].field_b { skip = true; },
+                    Modifier::I =>
+                        if core[destination_read] == core[source] { skip = true; },
+                }
+
+                if skip {
+                    process_queue[0].pointer += 2;
+                    step = false;
+                }
             }
-        }
-        Opcode::Slt => { // skips next instruction if instructions specified by A is less than by B
-            let mut skip: bool = false;
-
-            match instruction.modifier {
-                Modifier::A =>
-                    if core[destination].field_a.value >= core[source].field_a.value { skip = true; },
-                Modifier::B =>
-                    if core[destination].field_b.value >= core[source].field_b.value { skip = true; },
-                Modifier::AB =>
-                    if core[destination].field_b.value >= core[source].field_a.value { skip = true; },
-                Modifier::BA =>
-                    if core[destination].field_a.value >= core[source].field_b.value { skip = true; },
-                Modifier::F | Modifier::I =>
-                    if core[destination].field_a.value >= core[source].field_a.value && core[destination].field_b.value >= core[source].field_b.value { skip = true; },
-                Modifier::X =>
-                    if core[destination].field_b.value >= core[source].field_a.value && core[destination].field_a.value >= core[source].field_b.value { skip = true; },
+            Opcode::Sne => { // skips next instruction if instructions specified by A and B field are not equal
+                let mut skip: bool = false;
+
+                match instruction.modifier {
+                    Modifier::A =>
+                        if core[destination_read].field_a.value != core[source].field_a.value { skip = true; },
+                    Modifier::B =>
+                        if core[destination_read].field_b.value != core[source].field_b.value { skip = true; },
+                    Modifier::AB =>
+                        if core[destination_read].field_b.value != core[source].field_a.value { skip = true; },
+                    Modifier::BA =>
+                        if core[destination_read].field_a.value != core[source].field_b.value { skip = true; },
+                    Modifier::F =>
+                        if core[destination_read].field_a.value != core[source].field_a.value || core[destination_read].field_b.value != core[source].field_b.value { skip = true; },
+                    Modifier::X =>
+                        if core[destination_read].field_b.value != core[source].field_a.value || core[destination_read].field_a.value != core[source].field_b.value { skip = true; },
+                    Modifier::I =>
+                        if core[destination_read] != core[source] { skip = true; },
+                }
+
+                if skip {
+                    process_queue[0].pointer += 2;
+                    step = false;
+                }
             }
+            Opcode::Slt => { // skips next instruction if instructions specified by A is less than by B
+                let mut skip: bool = false;
 
-            if skip {
-                process_queue[0].pointer += 2;
-                step = false;
+                match instruction.modifier {
+                    Modifier::A =>
+                        if core[destination_read].field_a.value >= core[source].field_a.value { skip = true; },
+                    Modifier::B =>
+                        if core[destination_read].field_b.value >= core[source].field_b.value { skip = true; },
+                    Modifier::AB =>
+                        if core[destination_read].field_b.value >= core[source].field_a.value { skip = true; },
+                    Modifier::BA =>
+                        if core[destination_read].field_a.value >= core[source].field_b.value { skip = true; },
+                    Modifier::F | Modifier::I =>
+                        if core[destination_read].field_a.value >= core[source].field_a.value && core[destination_read].field_b.value >= core[source].field_b.value { skip = true; },
+                    Modifier::X =>
+                        if core[destination_read].field_b.value >= core[source].field_a.value && core[destination_read].field_a.value >= core[source].field_b.value { skip = true; },
+                }
+
+                if skip {
+                    process_queue[0].pointer += 2;
+                    step = false;
+                }
             }
+            Opcode::Ldp => { // loads the P-space cell at the index specified by A field into field specified by B field
+                let pspace = &self.pspaces[process.team as usize];
+                let pspace_size = pspace.len();
+                match instruction.modifier {
+                    Modifier::A =>
+                        core[destination].field_a.value = pspace[core[source].field_a.value % pspace_size],
+                    Modifier::BA =>
+                        core[destination].field_a.value = pspace[core[source].field_b.value % pspace_size],
+                    Modifier::AB =>
+                        core[destination].field_b.value = pspace[core[source].field_a.value % pspace_size],
+                    Modifier::B | Modifier::F | Modifier::X | Modifier::I =>
+                        core[destination].field_b.value = pspace[core[source].field_b.value % pspace_size],
+                }
+            }
+            Opcode::Stp => { // stores number specified by A field into the P-space cell at the index specified by B field
+                let pspace = &mut self.pspaces[process.team as usize];
+                let pspace_size = pspace.len();
+                let (index, value) = match instruction.modifier {
+                    Modifier::A =>
+                        (core[destination_read].field_a.value % pspace_size, core[source].field_a.value),
+                    Modifier::BA =>
+                        (core[destination_read].field_a.value % pspace_size, core[source].field_b.value),
+                    Modifier::AB =>
+                        (core[destination_read].field_b.value % pspace_size, core[source].field_a.value),
+                    Modifier::B | Modifier::F | Modifier::X | Modifier::I =>
+                        (core[destination_read].field_b.value % pspace_size, core[source].field_b.value),
+                };
+                saved_pspace_cell = Some((index, pspace[index]));
+                pspace[index] = value;
+            }
+            Opcode::Nop => { }
+        }
+
+        // process postincrements for field a
+        if instruction.field_a.address_mode == AddressMode::PostIncIndirectA {
+            increment_mod(&mut core[field_a_pointer].field_a.value, coresize);
+        } else if instruction.field_a.address_mode == AddressMode::PostIncIndirectB {
+            increment_mod(&mut core[field_a_pointer].field_b.value, coresize);
+        }
+
+        // process postincrements for field b
+        if instruction.field_b.address_mode == AddressMode::PostIncIndirectA {
+            increment_mod(&mut core[field_b_pointer].field_a.value, coresize);
+        } else if instruction.field_b.address_mode == AddressMode::PostIncIndirectB {
+            increment_mod(&mut core[field_b_pointer].field_b.value, coresize);
+        }
+
+        // like ICWS'94, the process moves to the back of the queue, followed by the one SPL split off
+        if dead {
+            process_queue.remove(0);
+        } else {
+            if step { process_queue[0].pointer += 1 };
+            process_queue[0].pointer %= coresize;
+            process_queue.rotate_left(1);
         }
-        // Opcode::Ldp => { // excuse me corewa.rs??
-        //
-        // }
-        // Opcode::Sdp => {
-        //
-        // }
-        Opcode::Nop => { }
+        if let Some(new_process) = new_process { process_queue.push_back(new_process); }
+
+        // DIV and MOD only write to the destination if they divided a field of it
+        let writes_destination = [Opcode::Mov, Opcode::Add, Opcode::Sub, Opcode::Mul, Opcode::Djn, Opcode::Ldp].contains(&instruction.opcode) || divided;
+        let written = [
+            [AddressMode::PreDecIndirectA, AddressMode::PreDecIndirectB, AddressMode::PostIncIndirectA, AddressMode::PostIncIndirectB].contains(&instruction.field_a.address_mode),
+            [AddressMode::PreDecIndirectA, AddressMode::PreDecIndirectB, AddressMode::PostIncIndirectA, AddressMode::PostIncIndirectB].contains(&instruction.field_b.address_mode),
+            writes_destination,
+        ];
+        return StepRecord {
+            cycle: self.cycle,
+            process,
+            instruction,
+            queue_length,
+            saved_cells: [saved_field_a_pointer, saved_field_b_pointer, saved_destination],
+            saved_owners: [None; 3],
+            written,
+            read_cells,
+            saved_pspace_cell,
+        };
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mars::MatchResult;
 
-    // process postincrements for field a
-    if instruction.field_a.address_mode == AddressMode::PostIncIndirectA {
-        increment_mod(&mut core[(instruction.field_a.value + process.pointer) % coresize].field_a.value, coresize);
-    } else if instruction.field_a.address_mode == AddressMode::PostIncIndirectB {
-        increment_mod(&mut core[(instruction.field_a.value + process.pointer) % coresize].field_b.value, coresize);
+    fn assemble(source: &str) -> Vec<Instruction> { // the instructions of source, as they'd be loaded into a core of 8000
+        let (parsed, _) = parse_warrior_source("test.red", source).unwrap_or_else(|error| panic!("{error}"));
+        return parsed.warrior.program.instructions.into_iter().map(|instruction| translate_instruction(instruction, 8000)).collect();
     }
 
-    // process postincrements for field b
-    if instruction.field_b.address_mode == AddressMode::PostIncIndirectA {
-        increment_mod(&mut core[(instruction.field_b.value + process.pointer) % coresize].field_a.value, coresize);
-    } else if instruction.field_b.address_mode == AddressMode::PostIncIndirectB {
-        increment_mod(&mut core[(instruction.field_b.value + process.pointer) % coresize].field_b.value, coresize);
+    #[test]
+    fn pspace_opcodes_after_labels() {
+        assert_eq!(rewrite_pspace_line("loop ldp.ab #0, 1", 10), "loop NOP.AB #(0)+10, 1");
+        assert_eq!(rewrite_pspace_line("save: stp 1, 2", 10), "save: NOP.B (1)-10, 2");
+        assert_eq!(rewrite_pspace_line("load:ldp 1, 2", 10), "load:NOP.B (1)+10, 2");
+        let instructions = assemble("start ldp.a 1, 2\nsave: stp.b 3, start\n      jmp start");
+        assert_eq!(instructions[0].opcode, Opcode::Ldp);
+        assert_eq!(instructions[1].opcode, Opcode::Stp);
+        assert_eq!(instructions[1].field_b.value, 8000 - 1);
     }
 
-    if dead {
-        process_queue.remove(0);
-    } else {
-        if step { process_queue[0].pointer += 1 };
-        process_queue[0].pointer %= coresize;
+    #[test]
+    fn pspace_opcode_modes_and_values() {
+        let instructions = assemble("ldp @2, <3\nstp {-1, >4\nldp #0, $-2");
+        assert_eq!((instructions[0].field_a.address_mode, instructions[0].field_a.value), (AddressMode::IndirectB, 2));
+        assert_eq!(instructions[0].field_b.address_mode, AddressMode::PreDecIndirectB);
+        assert_eq!((instructions[1].field_a.address_mode, instructions[1].field_a.value), (AddressMode::PreDecIndirectA, 8000 - 1));
+        assert_eq!(instructions[1].field_b.address_mode, AddressMode::PostIncIndirectB);
+        assert_eq!((instructions[2].field_a.address_mode, instructions[2].field_b.value), (AddressMode::Immediate, 8000 - 2));
     }
-}
 
-pub fn part_step(core: &mut Vec<Instruction>, coresize: usize, teams_process_queues: &mut Vec<VecDeque<Process>>, turn: &mut usize) { // steps the team whose turn it is
-    let mut process_queue = &mut teams_process_queues[*turn];
-    step_process(core, coresize, process_queue);
-    if process_queue.len() != 0 { 
-        process_queue.rotate_left(1); 
-        *turn += 1;
-        *turn %= teams_process_queues.len();
-    } else {
-        println!("Team {turn} has died!");
-        println!("Team {} wins!", (*turn + 1) % teams_process_queues.len());
-        teams_process_queues.remove(*turn);
-        if teams_process_queues.len() >= *turn {
-            *turn = 0;
+    #[test]
+    fn pspace_opcodes_in_comments() {
+        assert_eq!(rewrite_pspace_line("stp 1, 2 ; then ldp 3, 4", 10), "NOP.B (1)-10, 2; then ldp 3, 4");
+        assert_eq!(rewrite_pspace_line("; ldp 3, 4", 10), "; ldp 3, 4");
+        let instructions = assemble("; ldp 0, 0\nmov 0, 1 ; stp 0, 0");
+        assert_eq!(instructions.len(), 1);
+        assert_eq!(instructions[0].opcode, Opcode::Mov);
+    }
+
+    #[test]
+    fn pspace_opcode_default_modifiers() {
+        let instructions = assemble("ldp #0, 1\nldp 0, 1\nstp.f 0, 1\nstp #1, 1");
+        let modifiers: Vec<Modifier> = instructions.iter().map(|instruction| instruction.modifier).collect();
+        assert_eq!(modifiers, [Modifier::AB, Modifier::B, Modifier::F, Modifier::AB]);
+    }
+
+    #[test]
+    fn pspace_rewrite_keeps_line_numbers() {
+        let source = "ldp 0, 1\n\nstp.ab #0, 2 ; save\nstart jmp start\n  nop 600000000, 1";
+        let rewritten = rewrite_pspace_opcodes(source, PSPACE_MARKER);
+        assert_eq!(rewritten.lines().count(), source.lines().count());
+        assert_eq!(rewritten.lines().nth(3), Some("start jmp start"));
+        let error = parse_warrior_source("test.red", source).err().unwrap();
+        assert_eq!((error.diagnostics[0].path.as_deref(), error.diagnostics[0].line), (Some("test.red"), Some(5)));
+    }
+
+    #[test]
+    fn huge_nop_fields_are_rejected() {
+        assert_eq!(assemble("nop 5, 0")[0].opcode, Opcode::Nop);
+        let error = parse_warrior_source("test.red", "ldp 0, 1\nnop 600000000, 0\nstp -600000000, 1").err().unwrap();
+        assert_eq!(error.diagnostics.len(), 1);
+        assert_eq!(error.diagnostics[0].line, Some(2));
+    }
+
+    #[test]
+    fn pspace_opcodes_without_two_operands_are_rejected() {
+        for source in ["mov 0, 1\nldp 1\njmp -2", "start: stp.ab #1 ; no index\n  jmp start", "ldp.a 1, 2\nstp 1"] {
+            let error = parse_warrior_source("test.red", source).err().unwrap();
+            assert_eq!(error.diagnostics.len(), 1);
+            assert_eq!(error.diagnostics[0].severity, Severity::Error);
         }
+        let error = parse_warrior_source("test.red", "mov 0, 1\nldp 1\njmp -2").err().unwrap();
+        assert_eq!((error.diagnostics[0].line, error.diagnostics[0].message.as_str()), (Some(2), "expected additional arguments for LDP opcode"));
     }
-} 
-
-pub fn full_step(core: &mut Vec<Instruction>, coresize: usize, teams_process_queues: &mut Vec<VecDeque<Process>>, turn: &mut usize) { // steps until the turn is back to 0
-    part_step(core, coresize, teams_process_queues, turn);
-    while *turn != 0 && teams_process_queues.len() > 1 {
-        part_step(core, coresize, teams_process_queues, turn)
-    }
-}
-
-fn start_play_thread(old_app: &EmarsApp) {
-    let mut app = EmarsApp {
-        core: old_app.core.clone(),
-        coresize: old_app.coresize,
-        default_instruction: old_app.default_instruction,
-        core_view_size: old_app.core_view_size,
-        teams_process_queues: old_app.teams_process_queues.clone(),
-        turn: old_app.turn,
-        playing: old_app.playing,
-        play_delay: old_app.play_delay,
-        last_step: old_app.last_step,
-        state_sender: old_app.state_sender.clone(),
-        state_receiver: channel::<(Vec<Instruction>, Vec<VecDeque<Process>>)>().1,
-        play_step_count: old_app.play_step_count,
-        play_step_limit: old_app.play_step_limit,
-    };
-    spawn(move || {
-        loop {
-            if !app.process_playing() { break; }
-            sleep(Duration::from_millis(10))
+
+    #[test]
+    fn pspace_opcodes_that_cancel_the_marker_are_rejected() {
+        assert_eq!(assemble("stp -600000000, 1")[0].opcode, Opcode::Stp);
+        let error = parse_warrior_source("test.red", "ldp -600000000, 1").err().unwrap();
+        assert_eq!((error.diagnostics.len(), error.diagnostics[0].line), (1, Some(1)));
+        assert!(error.diagnostics[0].message.starts_with("LDP"));
+        let error = parse_warrior_source("test.red", "x equ 300000000
+mov 0, 1
+stp 2 * x, 1").err().unwrap();
+        assert_eq!((error.diagnostics.len(), error.diagnostics[0].line), (1, Some(3)));
+        assert!(error.diagnostics[0].message.starts_with("STP"));
+    }
+
+    #[test]
+    fn missing_labels_are_reported() {
+        let error = parse_warrior_source("test.red", "start mov 0, 1\n  jmp loop\n").err().unwrap();
+        assert_eq!(error.diagnostics.len(), 1);
+        assert_eq!(error.diagnostics[0].severity, Severity::Error);
+        assert_eq!(error.diagnostics[0].line, Some(2));
+        assert!(error.diagnostics[0].message.contains("loop"));
+        assert_eq!(find_missing_label("x equ 4\nstart: mov.ab #x, @start\norg start\nend\njmp nowhere"), None);
+        assert_eq!(find_missing_label("sequ equ 4\nmov #sequ, 1"), None);
+        assert_eq!(find_missing_label("start mov 0, 1\nend loop"), Some(("loop".to_string(), 2)));
+    }
+
+    #[test]
+    fn misspelled_opcodes_are_reported() {
+        let error = parse_warrior_source("test.red", "mov 0, 1\nmvo 0, 1\njmp -2").err().unwrap();
+        assert_eq!(error.diagnostics.len(), 1);
+        assert_eq!((error.diagnostics[0].severity, error.diagnostics[0].line), (Severity::Error, Some(2)));
+        for source in ["mov 0, 1\nmov.q 0, 1\njmp -2", "start spl.xy 0, 1\n  mov.i 0, 1"] {
+            let error = parse_warrior_source("test.red", source).err().unwrap();
+            assert_eq!(error.diagnostics.len(), 1);
+            assert!(error.diagnostics[0].message.contains("modifier"));
         }
-    });
-}
+        assert_eq!(parse_warrior_source("test.red", "mov 0, 1\nmov.q 0, 1").err().unwrap().diagnostics[0].line, Some(2));
+        let error = parse_warrior_source("test.red", "start mvo 0, 1\n  jmp start\n  dta #1").err().unwrap();
+        let lines: Vec<Option<usize>> = error.diagnostics.iter().map(|diagnostic| diagnostic.line).collect();
+        assert_eq!(lines, [Some(1), Some(3)]);
+        // labels on their own line, EQU names and whatever follows END aren't dropped instructions
+        assert!(parse_warrior_source("test.red", "imp equ mov 0, 1\nstart\n  imp\nend start\nnot redcode").is_ok());
+    }
 
-impl EmarsApp {
-    fn process_playing(&mut self) -> bool {
-        if self.last_step.elapsed().as_millis() as usize > self.play_delay {
-            let mut dead: bool = false;
-            for _ in 0..(self.last_step.elapsed().as_millis() as usize / self.play_delay) {
-                if self.play_step_count >= self.play_step_limit { return false; }
-                full_step(&mut self.core, self.coresize, &mut self.teams_process_queues, &mut self.turn);
-                self.play_step_count += 1;
-                if self.teams_process_queues.len() <= 1 { dead = true; break; }
-            }
-            self.last_step = Instant::now();
-            match self.state_sender.send((self.core.clone(), self.teams_process_queues.clone())) {
-                Ok(_) => {},
-                Err(_) => return false,
-            }
-            if dead { return false; }
+    #[test]
+    fn empty_warriors_are_rejected() {
+        let (parsed, _) = parse_warrior_source("test.red", "; just a comment\nstart").unwrap();
+        let diagnostics = check_warriors(&[parsed], &MarsConfig::new(8000));
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+    }
+
+    #[test]
+    fn warriors_over_the_max_length_are_rejected() {
+        let check = |max_length: usize| {
+            let (parsed, _) = parse_warrior_source("test.red", "mov 0, 1\nmov 0, 1\nmov 0, 1").unwrap();
+            let mut config = MarsConfig::new(8000);
+            config.max_length = max_length;
+            check_warriors(&[parsed], &config)
+        };
+        assert!(check(3).is_empty());
+        let diagnostics = check(2);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+    }
+
+    #[test]
+    fn max_cycles_ends_the_match_in_a_tie() {
+        let mut mars = load(&["jmp 0", "jmp 0"]);
+        mars.config.max_cycles = 20;
+        for _ in 0..19 { assert_eq!(mars.step(), MatchResult::Ongoing); }
+        assert_eq!(mars.step(), MatchResult::Tie);
+        assert_eq!((mars.cycle, mars.result()), (20, MatchResult::Tie));
+    }
+
+    #[test]
+    fn fold_keeps_pointers_within_half_the_limit() {
+        assert_eq!(fold(10, 400, 8000), 10);
+        assert_eq!(fold(200, 400, 8000), 200);
+        assert_eq!(fold(201, 400, 8000), 7801);
+        assert_eq!(fold(7999, 400, 8000), 7999);
+        assert_eq!(fold(7850, 400, 8000), 7850);
+        assert_eq!(fold(4000, 400, 8000), 0);
+        assert_eq!(fold(8300, 400, 8000), 7900);
+        assert_eq!(fold(123, 8000, 8000), 123);
+    }
+
+    #[test]
+    fn indirect_writes_follow_the_write_limit() {
+        // the B field folds to 300 within the read limit, but to -100 within the write limit
+        for (mode, destination) in [("@", 7907), ("<", 7906)] {
+            let mut mars = load(&[&format!("mov 0, {mode}300"), "jmp 0"]);
+            (mars.config.read_limit, mars.config.write_limit) = (800, 400);
+            mars.core[300].field_b.value = 5;
+            mars.core[7900].field_b.value = 7;
+            assert_eq!(mars.targets(0).1, destination);
+            mars.step();
+            assert!(mars.core[destination] == mars.core[0]);
+            assert_eq!(mars.core[300].field_b.value, 5);
+            assert_eq!(mars.core[7900].field_b.value, if mode == "<" { 6 } else { 7 });
         }
-        return true;
     }
 
-    pub fn press_play(&mut self) {
-        if !self.playing {
-            self.playing = true;
-            self.last_step = Instant::now();
-            start_play_thread(&self);
-        } else {
-            self.playing = false;
-            (self.state_sender, self.state_receiver) = channel::<(Vec<Instruction>, Vec<VecDeque<Process>>)>();
+    #[test]
+    fn indirect_reads_follow_the_read_limit() {
+        let mut mars = load(&["mov @300, 2", "jmp 0"]);
+        (mars.config.read_limit, mars.config.write_limit) = (800, 400);
+        mars.core[300].field_b.value = 5;
+        mars.core[7900].field_b.value = 7;
+        mars.core[305].field_a.value = 42;
+        assert_eq!(mars.targets(0).0, 305);
+        mars.step();
+        assert!(mars.core[2] == mars.core[305]);
+    }
+
+    #[test]
+    fn positions_of_missing_teams_are_rejected() {
+        let warriors = parse_test_warriors(&["jmp 0", "jmp 0"]);
+        let mut config = MarsConfig::new(8000);
+        config.positions = vec![None, Some(4000), None, None, None, Some(100)];
+        assert!(Mars::new(config.clone(), &warriors).is_err());
+        config.positions.truncate(5);
+        assert!(Mars::new(config, &warriors).is_ok());
+    }
+
+    fn check_icws88(source: &str) -> Vec<Diagnostic> { // the errors source has as ICWS'88
+        let (parsed, _) = parse_warrior_source("test.red", source).unwrap_or_else(|error| panic!("{error}"));
+        let mut config = MarsConfig::new(8000);
+        config.icws88 = true;
+        return check_warriors(&[parsed], &config);
+    }
+
+    #[test]
+    fn icws88_rejects_explicit_modifiers() {
+        assert_eq!(find_explicit_modifier("start mov.i 0, 1"), Some((1, "I".to_string())));
+        assert_eq!(find_explicit_modifier("mov 0, 1 ; not mov.i\nadd.ab #4, 3"), Some((2, "AB".to_string())));
+        assert_eq!(find_explicit_modifier("mov 0, 1\nadd #4, 3"), None);
+        // both match the modifier '88 implies, so only the source shows them
+        assert_eq!(check_icws88("mov.i 0, 1").len(), 1);
+        assert_eq!(check_icws88("add.ab #4, 3").len(), 1);
+        assert_eq!(check_icws88("add.f 4, 3").len(), 1);
+    }
+
+    #[test]
+    fn icws88_rejects_94_address_modes() {
+        for mode in [">", "*", "{", "}"] {
+            assert_eq!(check_icws88(&format!("mov {mode}1, 2")).len(), 1, "{mode}");
+            assert_eq!(check_icws88(&format!("mov 1, {mode}2")).len(), 1, "{mode}");
+        }
+        assert!(check_icws88("mov @1, <2").is_empty());
+    }
+
+    #[test]
+    fn icws88_dat_operands() {
+        assert_eq!(check_icws88("dat $0, #0").len(), 1);
+        assert_eq!(check_icws88("dat #0, @1").len(), 1);
+        assert!(check_icws88("dat #0, #0").is_empty());
+        assert!(check_icws88("dat <1, #2").is_empty());
+    }
+
+    #[test]
+    fn icws88_rejects_immediate_operands() {
+        assert_eq!(check_icws88("mov 0, #1").len(), 1);
+        assert_eq!(check_icws88("cmp 0, #1").len(), 1);
+        assert_eq!(check_icws88("jmp #1").len(), 1);
+        assert!(check_icws88("mov #1, 0").is_empty());
+        assert!(check_icws88("cmp #1, 0").is_empty());
+    }
+
+    #[test]
+    fn icws88_cmp_ignores_modifiers() {
+        for icws88 in [false, true] {
+            let mut mars = load(&["cmp 2, 3", "jmp 0"]);
+            mars.config.icws88 = icws88;
+            mars.core[2] = mars.core[0];
+            mars.core[3] = Instruction { modifier: Modifier::F, ..mars.core[0] };
+            mars.step();
+            // the next instruction is skipped only when the modifiers don't count
+            assert_eq!(mars.teams_process_queues[0][0].pointer, if icws88 { 2 } else { 1 });
         }
     }
+
+    fn load(sources: &[&str]) -> Mars { // a match in a core of 8000 with each warrior 1000 cells after the last
+        let warriors = parse_test_warriors(sources);
+        let mut config = MarsConfig::new(8000);
+        config.positions = (0..sources.len()).map(|team| Some(team * 1000)).collect();
+        return Mars::new(config, &warriors).unwrap_or_else(|error| panic!("{error}"));
+    }
+
+    fn pointers(mars: &Mars, team: usize) -> Vec<usize> {
+        return mars.teams_process_queues[team].iter().map(|process| process.pointer).collect();
+    }
+
+    #[test]
+    fn ldp_and_stp_move_values_between_core_and_pspace() {
+        // STP's index wraps around the P-space, and cell 0 holds -1 before the first round
+        let mut mars = load(&["stp.ab #42, #503\nldp.ab #3, 2\nldp.ab #0, 2\ndat 0, 0\ndat 0, 0", "jmp 0"]);
+        for _ in 0..3 { mars.step(); }
+        assert_eq!((mars.pspaces[0][3], mars.pspaces[0][503 % 500]), (42, 42));
+        assert_eq!(mars.core[3].field_b.value, 42);
+        assert_eq!(mars.core[4].field_b.value, 8000 - 1);
+        assert!(mars.pspaces[1].iter().skip(1).all(|value| *value == 0));
+    }
+
+    #[test]
+    fn spl_queues_the_new_process_behind_the_parent() {
+        let mut mars = load(&["jmp 0\nspl 3\njmp 0\njmp 0", "jmp 0"]);
+        mars.teams_process_queues[0] = VecDeque::from([Process { team: 0, pointer: 1 }, Process { team: 0, pointer: 3 }]);
+        mars.step();
+        assert_eq!(pointers(&mars, 0), [3, 2, 4]);
+    }
+
+    #[test]
+    fn spl_does_nothing_at_the_process_limit() {
+        let mut mars = load(&["spl 2\njmp 0\njmp 0", "jmp 0"]);
+        mars.config.max_processes = 1;
+        mars.step();
+        assert_eq!(pointers(&mars, 0), [1]);
+        mars.config.max_processes = 2;
+        mars.teams_process_queues[0][0].pointer = 0;
+        mars.step();
+        assert_eq!(pointers(&mars, 0), [1, 2]);
+    }
+
+    #[test]
+    fn division_by_zero_leaves_the_destination_alone() {
+        let mut mars = load(&["div.f x, 1\ndat #10, #10\nx dat #0, #2", "jmp 0"]);
+        mars.step();
+        assert_eq!((mars.core[1].field_a.value, mars.core[1].field_b.value), (10, 10));
+        assert!(!mars.is_alive(0));
+        assert_eq!(mars.last_steps[0].written, [false, false, false]);
+    }
+
+    #[test]
+    fn division_by_zero_only_writes_increments() {
+        let mut mars = load(&["mod.x x, }1\ndat #0, #0\nx dat #0, #0", "jmp 0"]);
+        mars.step();
+        assert_eq!(mars.core[1].field_a.value, 1);
+        assert!(!mars.is_alive(0));
+        assert_eq!(mars.last_steps[0].written, [false, true, false]);
+    }
 }
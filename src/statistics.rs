@@ -0,0 +1,200 @@
+use eframe::egui;
+use egui::*;
+use emars::{CycleStatistics, Mars};
+use emars::sim::Opcode;
+use crate::EmarsApp;
+use crate::gui::team_color;
+
+const CHART_HEIGHT: f32 = 120.;
+const OPCODE_WINDOW: usize = 100; // the number of cycles the opcode mix is averaged over
+
+// the statistics of every cycle of the match so far, oldest first, along with the chart lines drawn from them
+pub(crate) struct Statistics {
+    samples: Vec<CycleStatistics>,
+    // the lines are extended and cut back along with the samples, so they aren't worked out again every frame
+    cycles: Vec<usize>,
+    processes: Vec<Vec<f32>>, // each team's number of processes
+    owned_cells: Vec<Vec<f32>>, // each team's number of owned cells
+    opcode_shares: Vec<Vec<f32>>, // each opcode in Opcode::ALL's % of everything executed in the OPCODE_WINDOW cycles up to a sample
+    window_counts: [usize; Opcode::ALL.len()], // how often each opcode was executed in the last OPCODE_WINDOW samples
+    most_processes: usize,
+    most_owned_cells: usize,
+    executed: [bool; Opcode::ALL.len()], // which opcodes were executed at all, the others aren't charted
+}
+
+impl Statistics {
+    pub fn new() -> Statistics {
+        return Statistics {
+            samples: Vec::new(),
+            cycles: Vec::new(),
+            processes: Vec::new(),
+            owned_cells: Vec::new(),
+            opcode_shares: vec![Vec::new(); Opcode::ALL.len()],
+            window_counts: [0; Opcode::ALL.len()],
+            most_processes: 0,
+            most_owned_cells: 0,
+            executed: [false; Opcode::ALL.len()],
+        };
+    }
+
+    fn push(&mut self, sample: CycleStatistics) {
+        let teams = sample.processes.len();
+        self.processes.resize(teams, Vec::new());
+        self.owned_cells.resize(teams, Vec::new());
+        for team in 0..teams {
+            self.processes[team].push(sample.processes[team] as f32);
+            self.owned_cells[team].push(sample.owned_cells[team] as f32);
+        }
+        self.most_processes = self.most_processes.max(sample.processes.iter().max().copied().unwrap_or_default());
+        self.most_owned_cells = self.most_owned_cells.max(sample.owned_cells.iter().max().copied().unwrap_or_default());
+
+        // the window slides forward by one sample
+        for (opcode, count) in sample.opcodes.iter().enumerate() { self.window_counts[opcode] += count; }
+        if self.samples.len() >= OPCODE_WINDOW {
+            for (opcode, count) in self.samples[self.samples.len() - OPCODE_WINDOW].opcodes.iter().enumerate() { self.window_counts[opcode] -= count; }
+        }
+        let total: usize = self.window_counts.iter().sum();
+        for (opcode, count) in self.window_counts.iter().enumerate() {
+            let share = if total == 0 { 0. } else { *count as f32 / total as f32 * 100. };
+            self.opcode_shares[opcode].push(share);
+            self.executed[opcode] |= share > 0.;
+        }
+
+        self.cycles.push(sample.cycle);
+        self.samples.push(sample);
+    }
+
+    fn pop(&mut self) { // drops the newest sample, leaving the maxima and executed opcodes to recount
+        let Some(sample) = self.samples.pop() else { return; };
+        self.cycles.pop();
+        for line in self.processes.iter_mut().chain(&mut self.owned_cells).chain(&mut self.opcode_shares) {
+            line.pop();
+        }
+        // the window slides back by one sample
+        for (opcode, count) in sample.opcodes.iter().enumerate() { self.window_counts[opcode] -= count; }
+        if self.samples.len() >= OPCODE_WINDOW {
+            for (opcode, count) in self.samples[self.samples.len() - OPCODE_WINDOW].opcodes.iter().enumerate() { self.window_counts[opcode] += count; }
+        }
+    }
+
+    fn recount(&mut self) { // works out the maxima and executed opcodes again, after samples were dropped
+        self.most_processes = self.samples.iter().flat_map(|sample| sample.processes.iter()).max().copied().unwrap_or_default();
+        self.most_owned_cells = self.samples.iter().flat_map(|sample| sample.owned_cells.iter()).max().copied().unwrap_or_default();
+        self.executed = std::array::from_fn(|opcode| self.opcode_shares[opcode].iter().any(|share| *share > 0.));
+    }
+
+    pub fn extend(&mut self, samples: Vec<CycleStatistics>) {
+        for sample in samples { self.push(sample); }
+    }
+
+    pub fn update(&mut self, mars: &Mars) { // drops cycles that were stepped back past and records the current one if it's missing
+        if self.samples.last().is_some_and(|sample| sample.cycle > mars.cycle) {
+            while self.samples.last().is_some_and(|sample| sample.cycle > mars.cycle) {
+                self.pop();
+            }
+            self.recount();
+        }
+        // steps taken outside the play thread, like with the Step button, aren't recorded anywhere else
+        if self.samples.last().is_none_or(|sample| sample.cycle < mars.cycle) {
+            self.push(mars.statistics());
+        }
+    }
+
+    pub fn clear(&mut self) {
+        *self = Statistics::new();
+    }
+}
+
+fn opcode_color(index: usize) -> Color32 {
+    let hue = index as f32 / Opcode::ALL.len() as f32;
+    return ecolor::Hsva::new(hue, 0.7, 1., 1.).into();
+}
+
+fn line_chart(ui: &mut Ui, cycles: &[usize], lines: &[(Color32, &[f32])], y_max: f32) { // draws lines of values against cycles, from 0 to y_max
+    let (response, painter) = ui.allocate_painter(vec2(ui.available_width(), CHART_HEIGHT), Sense::hover());
+    let rect = response.rect;
+    painter.rect_stroke(rect, CornerRadius::same(2), Stroke::new(1., Color32::DARK_GRAY), StrokeKind::Inside);
+    let (Some(first), Some(last)) = (cycles.first(), cycles.last()) else { return; };
+
+    let y_max = y_max.max(1.);
+    let cycle_span = (last - first).max(1) as f32;
+    // only about one point per pixel is drawn, so long matches stay fast
+    let stride = (cycles.len() / rect.width().max(1.) as usize).max(1);
+    for (color, values) in lines {
+        let points: Vec<Pos2> = (0..cycles.len()).step_by(stride).chain([cycles.len() - 1])
+            .map(|i| pos2(
+                rect.left() + (cycles[i] - first) as f32 / cycle_span * rect.width(),
+                rect.bottom() - values[i] / y_max * rect.height(),
+            ))
+            .collect();
+        painter.add(Shape::line(points, Stroke::new(1.5, *color)));
+    }
+
+    let font = FontId::monospace(10.);
+    painter.text(rect.left_top() + vec2(3., 2.), Align2::LEFT_TOP, format!("{y_max}"), font.clone(), Color32::GRAY);
+    painter.text(rect.left_bottom() + vec2(3., -2.), Align2::LEFT_BOTTOM, format!("cycle {first}"), font.clone(), Color32::GRAY);
+    painter.text(rect.right_bottom() + vec2(-3., -2.), Align2::RIGHT_BOTTOM, format!("cycle {last}"), font, Color32::GRAY);
+}
+
+pub fn statistics_window(app: &mut EmarsApp, context: &Context) {
+    Window::new("Statistics")
+    .default_open(false)
+    .default_width(400.)
+    .show(context, |ui| {
+        let statistics = &app.statistics;
+        let teams = app.mars.teams_process_queues.len().min(statistics.processes.len());
+
+        ui.horizontal_wrapped(|ui| {
+            for team in 0..teams {
+                ui.colored_label(team_color(team as u8), format!("Team {team}: {}", app.mars.warrior_names[team]));
+            }
+        });
+
+        ui.strong("Processes");
+        let processes: Vec<(Color32, &[f32])> = (0..teams).map(|team| (team_color(team as u8), &statistics.processes[team][..])).collect();
+        line_chart(ui, &statistics.cycles, &processes, statistics.most_processes as f32);
+
+        ui.strong("Owned cells");
+        let owned_cells: Vec<(Color32, &[f32])> = (0..teams).map(|team| (team_color(team as u8), &statistics.owned_cells[team][..])).collect();
+        line_chart(ui, &statistics.cycles, &owned_cells, statistics.most_owned_cells as f32);
+
+        ui.strong(format!("Opcode mix over the last {OPCODE_WINDOW} cycles, in %"));
+        // opcodes that were never executed are left out
+        let executed: Vec<usize> = (0..Opcode::ALL.len()).filter(|opcode| statistics.executed[*opcode]).collect();
+        let opcode_lines: Vec<(Color32, &[f32])> = executed.iter().map(|opcode| (opcode_color(*opcode), &statistics.opcode_shares[*opcode][..])).collect();
+        line_chart(ui, &statistics.cycles, &opcode_lines, 100.);
+        ui.horizontal_wrapped(|ui| {
+            for opcode in executed {
+                ui.colored_label(opcode_color(opcode), Opcode::ALL[opcode].to_string());
+            }
+        });
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(cycle: usize) -> CycleStatistics { // a made-up cycle whose numbers change from one to the next
+        let mut opcodes = [0; Opcode::ALL.len()];
+        opcodes[cycle % 3] = 1 + cycle % 2;
+        return CycleStatistics { cycle, processes: vec![cycle % 7, 1], owned_cells: vec![cycle % 50, 20], opcodes };
+    }
+
+    #[test]
+    fn dropped_samples_leave_the_lines_as_if_they_were_never_added() {
+        let mut statistics = Statistics::new();
+        statistics.extend((1..=300).map(sample).collect());
+        while statistics.samples.len() > 150 { statistics.pop(); }
+        statistics.recount();
+        let mut expected = Statistics::new();
+        expected.extend((1..=150).map(sample).collect());
+        assert_eq!(statistics.cycles, expected.cycles);
+        assert_eq!(statistics.processes, expected.processes);
+        assert_eq!(statistics.owned_cells, expected.owned_cells);
+        assert_eq!(statistics.opcode_shares, expected.opcode_shares);
+        assert_eq!(statistics.window_counts, expected.window_counts);
+        assert_eq!((statistics.most_processes, statistics.most_owned_cells), (expected.most_processes, expected.most_owned_cells));
+        assert_eq!(statistics.executed, expected.executed);
+    }
+}